@@ -1,5 +1,7 @@
 cosmic-app-library = COSMIC App Library
 cosmic-library-home = Library Home
+cosmic-library-favorites = Favorites
+cosmic-library-history = History
 cosmic-office = Office
 cosmic-system = System
 cosmic-utilities = Utilities
@@ -8,21 +10,104 @@ name = Name
 ok = Ok
 save = Save
 cancel = Cancel
+close = Close
+refresh = Refresh
 search-placeholder = Type to search apps...
+search-syntax = Search by name with name:, by category with cat:, or by ID with id:. Start with = to match a name exactly.
 new-group-placeholder = Folder Name
+add-to-favorites = Add to Favorites
+remove-from-favorites = Remove from Favorites
 pin-to-app-tray = Pin to App Tray
+pin-to-app-tray-start = Pin to start of App Tray
+pin-all-to-app-tray = Pin all to App Tray
+unpin-all-from-app-tray = Unpin all from App Tray
+open-all = Open all apps
+open-all-confirm = Open all {$count} apps?
+add-to-folder = Add to folder
+edit-desktop-file = Edit Desktop File
 run = Run
 run-on = Run on {$gpu}
 run-on-default = (Default)
+run-on-default-gpu = Run on {$gpu} (Default)
+run-in-terminal = Run in Terminal
+run-detached = Run Detached
+run-variant = Run {$source} version
 remove = Move to library home
 create-new = Create new folder
 add-group = Add group
 delete = Delete
 rename = Rename
+edit-filter = Edit filter
+edit-icon = Edit icon
+icon = Icon
+icon-placeholder = Icon name
+categories = Categories
+include = Include
+exclude = Exclude
+match-all-categories = Match all categories
+filter-matches = { $count ->
+    [0] No apps match this filter.
+    [one] 1 app matches this filter.
+   *[other] { $count } apps match this filter.
+}
+accent-color = Accent color
+always-included = always included
+always-excluded = always excluded
 delete-folder = Delete folder?
-    .msg = Deleting this folder will move the application icons to Library home.
+    .msg = Only the folder will be removed. The apps in it won't be uninstalled.
+delete-folder-apps = { $count ->
+    [one] This folder contains 1 app.
+   *[other] This folder contains { $count } apps.
+}
+delete-folder-dynamic = This folder is filled automatically by category.
 flatpak = Flatpak
+flatpak-user = Flatpak (user)
+flatpak-system = Flatpak (system)
 snap = Snap
 system = System
 local = Local
 nix = Nix
+app-position = {$position} of {$count}
+no-apps = No applications found
+showing-results = Showing {$shown} of {$total} apps
+no-apps-search-hint = Try a different search, or install the app from the App Store.
+no-apps-group-hint = Drag apps here to add them.
+no-apps-history-hint = Apps you launch will be listed here.
+no-apps-installed = No applications installed
+no-apps-installed-hint = Apps you install from the App Store will appear here.
+open-app-store = Open App Store
+clear-history = Clear history
+launch-options = Launch Options…
+launch-options-title = Launch options for {$app}
+environment-variable = Environment variable
+sort-order = Sort apps
+sort-alphabetical = Alphabetically
+sort-frecency = By usage
+sort-manual = Manually
+keyboard-shortcuts = Keyboard shortcuts
+move-left = Highlight the previous app
+move-right = Highlight the next app
+move-up = Highlight the app above
+move-down = Highlight the app below
+toggle-group-focus = Switch between apps and folders
+next-group = Next folder
+previous-group = Previous folder
+select-group = Go to a folder
+toggle-home = Switch between Library Home and the last folder
+select-apps = Select apps
+select-apps-hint = Click apps to select them, then click a folder to add them to it.
+selected-apps = { $count ->
+    [one] 1 app selected
+   *[other] { $count } apps selected
+}
+remove-from-folder = Remove from folder
+done = Done
+launch-count = Launched { $count ->
+    [one] once
+   *[other] { $count } times
+}, last opened { $days ->
+    [0] today
+    [1] yesterday
+   *[other] { $days } days ago
+}
+already-in-folder = Already here
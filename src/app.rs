@@ -1,12 +1,15 @@
 use std::{
-    collections::HashMap,
+    cell::RefCell,
+    collections::{HashMap, HashSet},
     fmt::{Debug, Display},
+    io::Write,
+    iter,
     path::{Path, PathBuf},
     rc::Rc,
     time::{Duration, Instant},
 };
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use cosmic::{
     app::{Core, CosmicFlags, DbusActivationDetails, DbusActivationMessage, Settings, Task},
     cctk::sctk::{
@@ -16,11 +19,11 @@ use cosmic::{
     },
     cosmic_config::{Config, CosmicConfigEntry},
     cosmic_theme::Spacing,
-    desktop::{load_desktop_file, DesktopEntryData},
+    desktop::{load_desktop_file, DesktopAction, DesktopEntryData, IconSource},
     iced::{
         self,
         alignment::Horizontal,
-        event::listen_with,
+        event::{self, listen_with},
         executor,
         id::Id,
         /*wayland::actions::{
@@ -31,13 +34,13 @@ use cosmic::{
     },
     iced_core::{
         alignment::Vertical,
-        keyboard::{key::Named, Key},
-        Border, Padding, Rectangle, Shadow,
+        keyboard::{key::Named, Key, Modifiers},
+        mouse, touch, Border, Padding, Rectangle, Shadow,
     },
     iced_runtime::{
         core::{
             event::{
-                wayland::{self, LayerEvent},
+                wayland::{self, LayerEvent, PopupEvent},
                 PlatformSpecific,
             },
             window::Id as SurfaceId,
@@ -59,44 +62,89 @@ use cosmic::{
     widget::{
         autosize::autosize,
         button::{self, Catalog as ButtonStyleSheet},
-        divider,
+        checkbox, divider,
         dnd_destination::dnd_destination_for_data,
         icon::{self, from_name},
-        search_input, svg,
-        text::body,
+        menu::key_bind::KeyBind,
+        radio, search_input, svg,
+        text::{body, caption},
         text_input, tooltip, Column,
     },
     Element,
 };
 use cosmic_app_list_config::AppListConfig;
-use freedesktop_desktop_entry::PathSource;
+use freedesktop_desktop_entry::{DesktopEntry, PathSource};
 use itertools::Itertools;
 use log::error;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use switcheroo_control::Gpu;
+use tokio::sync::mpsc;
 
 use crate::{
-    app_group::AppLibraryConfig,
-    fl,
-    subscriptions::desktop_files::desktop_files,
-    widgets::application::{AppletString, ApplicationButton},
+    app_group::{
+        parse_accent, AppGroup, AppLibraryConfig, FilterType, GroupOrder, SortOrder, THEME_ACCENTS,
+    },
+    app_state::AppLibraryState,
+    diagnostics, fl,
+    key_bind::{self, Action},
+    subscriptions::{
+        desktop_files::desktop_files,
+        status::{self, Status},
+    },
+    widgets::{
+        application::{
+            named_app_icon, AppletString, ApplicationButton, DRAG_THRESHOLD, TOUCH_DRAG_THRESHOLD,
+        },
+        group::GroupButton,
+    },
 };
 
 // popovers should show options, but also the desktop info options
 // should be a way to add apps to groups
 // should be a way to remove apps from groups
 
+const MAX_WIDTH: f32 = 1200.0;
+const APPS_PER_ROW: usize = 7;
+/// The height of an app tile with two lines for its name, including its padding.
+const APP_TILE_HEIGHT: f32 = 144.0;
+/// The height of a line of an app name.
+const APP_NAME_LINE_HEIGHT: f32 = 20.0;
+const APP_GRID_MAX_HEIGHT: f32 = 444.0;
+/// The rows of apps built above and below the ones in view, so they are ready when scrolling.
+const APP_GRID_ROW_BUFFER: usize = 2;
+const ANIMATION_DURATION: Duration = Duration::from_millis(150);
+/// How long to wait for an activation token before launching without one.
+const ACTIVATION_TOKEN_TIMEOUT: Duration = Duration::from_millis(500);
+/// The software store which is opened when no apps are installed.
+const APP_STORE_ID: &str = "com.system76.CosmicStore";
+const APP_STORE_EXEC: &str = "cosmic-store";
+/// The time between launching the apps of a group, so they don't all start at once.
+const OPEN_ALL_STAGGER: Duration = Duration::from_millis(250);
+/// Opening more apps of a group than this at once has to be confirmed.
+const OPEN_ALL_CONFIRM_COUNT: usize = 5;
+const PIXELS_PER_LINE: f32 = 40.0;
+const GROUP_SWITCH_OVERSCROLL: f32 = 3.0;
+/// With more groups than this, they are shown compact unless configured otherwise.
+const COMPACT_GROUPS_THRESHOLD: usize = 15;
+/// The longest group name which fits on a group tile.
+const MAX_GROUP_TILE_NAME_LEN: usize = 14;
+/// The longest group name which fits in the header.
+const MAX_GROUP_HEADER_NAME_LEN: usize = 32;
+
 static SEARCH_ID: Lazy<Id> = Lazy::new(|| Id::new("search"));
 static EDIT_GROUP_ID: Lazy<Id> = Lazy::new(|| Id::new("edit_group"));
 static NEW_GROUP_ID: Lazy<Id> = Lazy::new(|| Id::new("new_group"));
 static SUBMIT_DELETE_ID: Lazy<Id> = Lazy::new(|| Id::new("cancel_delete"));
+static LAUNCH_ENV_ID: Lazy<Id> = Lazy::new(|| Id::new("launch_env"));
+static EDIT_GROUP_ICON_ID: Lazy<Id> = Lazy::new(|| Id::new("edit_group_icon"));
 
 static CREATE_NEW: Lazy<String> = Lazy::new(|| fl!("create-new"));
 static ADD_GROUP: Lazy<String> = Lazy::new(|| fl!("add-group"));
 static SEARCH_PLACEHOLDER: Lazy<String> = Lazy::new(|| fl!("search-placeholder"));
 static NEW_GROUP_PLACEHOLDER: Lazy<String> = Lazy::new(|| fl!("new-group-placeholder"));
+static ENVIRONMENT_VARIABLE: Lazy<String> = Lazy::new(|| fl!("environment-variable"));
 static SAVE: Lazy<String> = Lazy::new(|| fl!("save"));
 static CANCEL: Lazy<String> = Lazy::new(|| fl!("cancel"));
 static RUN: Lazy<String> = Lazy::new(|| fl!("run"));
@@ -108,22 +156,52 @@ static SNAP: Lazy<String> = Lazy::new(|| fl!("snap"));
 static SYSTEM: Lazy<String> = Lazy::new(|| fl!("system"));
 
 pub(crate) static WINDOW_ID: Lazy<SurfaceId> = Lazy::new(|| SurfaceId::unique());
-static NEW_GROUP_WINDOW_ID: Lazy<SurfaceId> = Lazy::new(|| SurfaceId::unique());
+static NEW_GROUP_POPUP_ID: Lazy<SurfaceId> = Lazy::new(|| SurfaceId::unique());
 static NEW_GROUP_AUTOSIZE_ID: Lazy<cosmic::widget::Id> = Lazy::new(|| cosmic::widget::Id::unique());
-static DELETE_GROUP_WINDOW_ID: Lazy<SurfaceId> = Lazy::new(|| SurfaceId::unique());
+static DELETE_GROUP_POPUP_ID: Lazy<SurfaceId> = Lazy::new(|| SurfaceId::unique());
 static DELETE_GROUP_AUTOSIZE_ID: Lazy<cosmic::widget::Id> =
     Lazy::new(|| cosmic::widget::Id::unique());
+static EDIT_GROUP_FILTER_WINDOW_ID: Lazy<SurfaceId> = Lazy::new(|| SurfaceId::unique());
+static EDIT_GROUP_FILTER_AUTOSIZE_ID: Lazy<cosmic::widget::Id> =
+    Lazy::new(|| cosmic::widget::Id::unique());
+static HELP_WINDOW_ID: Lazy<SurfaceId> = Lazy::new(|| SurfaceId::unique());
+static HELP_AUTOSIZE_ID: Lazy<cosmic::widget::Id> = Lazy::new(|| cosmic::widget::Id::unique());
+static LAUNCH_OPTIONS_WINDOW_ID: Lazy<SurfaceId> = Lazy::new(|| SurfaceId::unique());
+static LAUNCH_OPTIONS_AUTOSIZE_ID: Lazy<cosmic::widget::Id> =
+    Lazy::new(|| cosmic::widget::Id::unique());
 pub(crate) static MENU_ID: Lazy<SurfaceId> = Lazy::new(|| SurfaceId::unique());
 pub(crate) static MENU_AUTOSIZE_ID: Lazy<cosmic::widget::Id> =
     Lazy::new(|| cosmic::widget::Id::unique());
+pub(crate) static GROUP_MENU_ID: Lazy<SurfaceId> = Lazy::new(|| SurfaceId::unique());
+pub(crate) static GROUP_MENU_AUTOSIZE_ID: Lazy<cosmic::widget::Id> =
+    Lazy::new(|| cosmic::widget::Id::unique());
 
 #[derive(Parser, Debug, Serialize, Deserialize, Clone)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
-pub struct Args {}
+pub struct Args {
+    #[command(subcommand)]
+    pub subcommand: Option<LauncherCommands>,
+}
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct LauncherCommands;
+#[derive(Subcommand, Debug, Serialize, Deserialize, Clone)]
+pub enum LauncherCommands {
+    /// Print the apps and the groups they are in as JSON, without opening the library
+    List,
+    /// Print why desktop entries are missing from the library or shown differently than expected
+    Validate,
+    /// Make the running library look for installed and removed apps
+    Refresh,
+    /// Open the library as just a search box, which shows the apps once something is typed
+    Search,
+    /// Remove the custom groups and settings, and forget the recent searches and launched apps.
+    /// They are backed up first, next to where they are stored
+    Reset {
+        /// Don't ask for confirmation
+        #[arg(long)]
+        yes: bool,
+    },
+}
 
 impl ToString for LauncherCommands {
     fn to_string(&self) -> String {
@@ -136,11 +214,34 @@ impl CosmicFlags for Args {
     type Args = Vec<String>;
 
     fn action(&self) -> Option<&LauncherCommands> {
-        None
+        self.subcommand.as_ref()
     }
 }
 
 pub fn run() -> cosmic::iced::Result {
+    let mut args = Args::parse();
+    match args.subcommand {
+        Some(LauncherCommands::List) => {
+            list_apps();
+            return Ok(());
+        }
+        Some(LauncherCommands::Validate) => {
+            diagnostics::validate();
+            return Ok(());
+        }
+        Some(LauncherCommands::Reset { yes: false }) => {
+            if !confirm_reset() {
+                return Ok(());
+            }
+            args.subcommand = Some(LauncherCommands::Reset { yes: true });
+        }
+        Some(
+            LauncherCommands::Refresh
+            | LauncherCommands::Search
+            | LauncherCommands::Reset { yes: true },
+        )
+        | None => {}
+    }
     cosmic::app::run_single_instance::<CosmicAppLibrary>(
         Settings::default()
             .antialiasing(true)
@@ -150,13 +251,44 @@ pub fn run() -> cosmic::iced::Result {
             .scale_factor(1.0)
             .no_main_window(true)
             .exit_on_close(false),
-        Args::parse(),
+        args,
     )
 }
 
-pub struct AppSource(PathSource);
+/// Asks on the terminal whether to reset the library.
+fn confirm_reset() -> bool {
+    println!(
+        "This removes the custom groups and settings of the library, and forgets the recent searches and launched apps."
+    );
+    print!("Reset the library? [y/N] ");
+    _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err()
+        || !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+    {
+        println!("Not reset. Pass --yes to reset without being asked.");
+        return false;
+    }
+    true
+}
+
+pub struct AppSource(PathSource, Option<String>);
 
 impl AppSource {
+    /// A longer description of the source than the label, including the flatpak ref if known.
+    pub fn description(&self) -> String {
+        let source = match &self.0 {
+            PathSource::LocalFlatpak => fl!("flatpak-user"),
+            PathSource::SystemFlatpak => fl!("flatpak-system"),
+            PathSource::Other(s) => s.clone(),
+            _ => self.to_string(),
+        };
+        match &self.1 {
+            Some(flatpak_ref) => format!("{source}: {flatpak_ref}"),
+            None => source,
+        }
+    }
+
     pub fn as_icon(&self) -> Option<icon::Icon> {
         let name = match &self.0 {
             PathSource::Local | PathSource::LocalDesktop => "app-source-local-symbolic",
@@ -180,12 +312,45 @@ impl AppSource {
     }
 }
 
+impl AppSource {
+    /// The kind of source, as used in the source order of the config.
+    pub fn kind(&self) -> &'static str {
+        match &self.0 {
+            PathSource::Local | PathSource::LocalDesktop => "local",
+            PathSource::System | PathSource::SystemLocal => "system",
+            PathSource::LocalFlatpak | PathSource::SystemFlatpak => "flatpak",
+            PathSource::SystemSnap => "snap",
+            PathSource::Nix | PathSource::LocalNix => "nix",
+            PathSource::Other(_) => "other",
+        }
+    }
+}
+
 impl<'a> From<&'a Path> for AppSource {
     fn from(path: &'a Path) -> Self {
-        AppSource(PathSource::guess_from(path))
+        let source = PathSource::guess_from(path);
+        let flatpak_ref = matches!(source, PathSource::LocalFlatpak | PathSource::SystemFlatpak)
+            .then(|| flatpak_ref(path))
+            .flatten();
+        AppSource(source, flatpak_ref)
     }
 }
 
+/// Reads the `<id>/<arch>/<branch>` of a flatpak from the deployment its exported desktop file links to.
+fn flatpak_ref(path: &Path) -> Option<String> {
+    let path = path.canonicalize().ok()?;
+    let components: Vec<_> = path
+        .components()
+        .filter_map(|c| c.as_os_str().to_str())
+        .collect();
+    // .../app/<id>/<arch>/<branch>/<commit>/export/share/applications/<id>.desktop
+    let export = components.iter().rposition(|c| *c == "export")?;
+    let [id, arch, branch, _commit] = components.get(export.checked_sub(4)?..export)? else {
+        return None;
+    };
+    Some(format!("{id}/{arch}/{branch}"))
+}
+
 impl<'a> Display for AppSource {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -209,23 +374,180 @@ struct CosmicAppLibrary {
     entry_path_input: Vec<Arc<DesktopEntryData>>,
     all_entries: Vec<Arc<DesktopEntryData>>,
     menu: Option<usize>,
+    /// The app launched when pressing Enter in the search input.
+    highlighted: Option<usize>,
+    /// The ID of the highlighted app while the apps are reloaded, to highlight it again after.
+    reload_highlight: Option<String>,
+    /// The group focused with the keyboard, which the arrow keys move between instead of the apps.
+    highlighted_group: Option<usize>,
+    /// The group shown before the current one, to switch back to from Library Home.
+    previous_group: usize,
+    /// Whether the library is only a search box until something is typed.
+    search_only: bool,
+    /// Whether clicking apps selects them, to organize them together.
+    selecting: bool,
+    /// The IDs of the selected apps.
+    selection: HashSet<String>,
+    menu_info: ContextMenuInfo,
     helper: Option<Config>,
     config: AppLibraryConfig,
+    state_helper: Option<Config>,
+    state: AppLibraryState,
     cur_group: usize,
     active_surface: bool,
     locale: Option<String>,
     edit_name: Option<String>,
     new_group: Option<String>,
     dnd_icon: Option<usize>,
+    /// The app in front of which a dragged app would be placed.
+    reorder_target: Option<usize>,
+    /// Whether the current drag was dropped within its own group, so it stays there.
+    reordered: bool,
     offer_group: Option<usize>,
+    /// Whether the app dragged over `offer_group` is already in it.
+    offer_contains: bool,
     waiting_for_filtered: bool,
+    /// Launches waiting for an activation token, by the serial they were given.
+    pending_launches: HashSet<u64>,
+    next_launch: u64,
+    /// The number of apps matching the search, of which only `max_results` may be shown.
+    total_results: usize,
     scroll_offset: f32,
     core: Core,
     group_to_delete: Option<usize>,
+    group_editor: Option<GroupEditor>,
+    launch_options_editor: Option<LaunchOptionsEditor>,
+    help: bool,
+    key_binds: Vec<(KeyBind, Action)>,
+    /// A config changed outside of the library while groups were being edited.
+    pending_config: Option<AppLibraryConfig>,
     gpus: Option<Vec<Gpu>>,
     last_hide: Option<Instant>,
+    anim_progress: f32,
+    anim_start: Option<Instant>,
+    hiding: bool,
+    /// Whether the last press was on a touchscreen rather than with a pointer.
+    touch_input: bool,
+    modifiers: Modifiers,
+    window_width: f32,
+    scroll_relative: Option<f32>,
+    /// How far the apps have been scrolled past their ends, in lines.
+    overscroll: f32,
+    /// The sources shown on the tiles of apps, by path.
     duplicates: HashMap<PathBuf, AppSource>,
+    warmed_icons: HashSet<String>,
+    /// The IDs of entries which are only shown because of `show_hidden_desktop_entries`.
+    hidden_entries: HashSet<String>,
+    /// The lowercased comments of the entries by ID, which searches also match.
+    comments: HashMap<String, String>,
+    /// Variants of an entry which were collapsed into it, in order of preference.
+    alternates: HashMap<PathBuf, Vec<Arc<DesktopEntryData>>>,
     app_list_config: AppListConfig,
+    /// The group whose context menu is open.
+    group_menu: Option<usize>,
+    /// The bounds of the group tile whose menu is open, which its dialogs are anchored to.
+    group_menu_bounds: Rectangle,
+    /// The number of apps to open from the group menu, once confirmed.
+    open_all_confirm: Option<usize>,
+    /// Sends what the library shows to the status D-Bus interface.
+    status_tx: Option<mpsc::UnboundedSender<Status>>,
+}
+
+/// Details about the entry of the open context menu, gathered when it is opened.
+#[derive(Default)]
+struct ContextMenuInfo {
+    editable: bool,
+    /// The version of the app, if its desktop entry has one.
+    version: Option<String>,
+    /// The desktop actions in the order they are declared, with their icons.
+    actions: Vec<(DesktopAction, Option<String>)>,
+}
+
+impl ContextMenuInfo {
+    fn new(entry: &DesktopEntryData) -> Self {
+        let editable = entry
+            .path
+            .as_ref()
+            .is_some_and(|path| nix::unistd::access(path, nix::unistd::AccessFlags::W_OK).is_ok());
+
+        let desktop_entry = entry
+            .path
+            .as_ref()
+            .and_then(|path| DesktopEntry::from_path::<&str>(path, None).ok());
+        let version = desktop_entry
+            .as_ref()
+            .and_then(|de| de.desktop_entry("X-AppVersion"))
+            .map(str::to_string);
+
+        // the exec and icon of each action listed in the `Actions` key
+        let declared: Vec<(String, Option<String>)> = desktop_entry
+            .as_ref()
+            .map(|de| {
+                de.actions()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|id| {
+                        Some((
+                            de.action_exec(id)?.to_string(),
+                            de.action_entry(id, "Icon").map(str::to_string),
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut actions: Vec<_> = entry
+            .desktop_actions
+            .iter()
+            .map(|action| {
+                let pos = declared.iter().position(|(exec, _)| exec == &action.exec);
+                let icon = pos.and_then(|pos| declared[pos].1.clone());
+                (pos.unwrap_or(usize::MAX), action.clone(), icon)
+            })
+            .collect();
+        actions.sort_by_key(|(pos, ..)| *pos);
+
+        Self {
+            editable,
+            version,
+            actions: actions
+                .into_iter()
+                .map(|(_, action, icon)| (action, icon))
+                .collect(),
+        }
+    }
+}
+
+struct LaunchOptionsEditor {
+    app_id: String,
+    app_name: String,
+    env: Vec<(String, String)>,
+    input: String,
+}
+
+impl LaunchOptionsEditor {
+    /// The variable typed in the input, if it is a `NAME=value`.
+    fn parsed_input(&self) -> Option<(String, String)> {
+        let (key, value) = self.input.split_once('=')?;
+        let key = key.trim();
+        (!key.is_empty()).then(|| (key.to_string(), value.to_string()))
+    }
+
+    /// Adds the variable typed in the input, replacing any with the same name.
+    fn add_input(&mut self) {
+        if let Some((key, value)) = self.parsed_input() {
+            self.env.retain(|(k, _)| *k != key);
+            self.env.push((key, value));
+            self.input.clear();
+        }
+    }
+}
+
+struct GroupEditor {
+    group: usize,
+    filter: FilterType,
+    accent: Option<String>,
+    icon: String,
+    sort: SortOrder,
 }
 
 async fn try_get_gpus() -> Option<Vec<Gpu>> {
@@ -245,20 +567,400 @@ async fn try_get_gpus() -> Option<Vec<Gpu>> {
     Some(gpus)
 }
 
+/// The command to open a file in the default text editor. Desktop entries are often opened by
+/// launching them, so the handler of their own type can't be used to edit them.
+async fn text_editor_exec(path: &Path) -> Option<String> {
+    let path = shlex::try_quote(path.to_str()?).ok()?.into_owned();
+    let handler = tokio::process::Command::new("xdg-mime")
+        .args(["query", "default", "text/plain"])
+        .output()
+        .await
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|handler| handler.trim().to_string())
+        .filter(|handler| !handler.is_empty());
+    let exec = handler
+        .and_then(|handler| {
+            freedesktop_desktop_entry::default_paths()
+                .map(|dir| dir.join(&handler))
+                .find(|entry| entry.exists())
+        })
+        .and_then(|entry| DesktopEntry::from_path::<&str>(entry, None).ok())
+        .and_then(|entry| entry.exec().and_then(shlex::split));
+    if let Some(exec) = exec {
+        let mut args: Vec<_> = exec
+            .into_iter()
+            .filter_map(|arg| match arg.as_str() {
+                "%f" | "%F" | "%u" | "%U" => Some(path.clone()),
+                _ if arg.starts_with('%') => None,
+                _ => shlex::try_quote(&arg).ok().map(|arg| arg.into_owned()),
+            })
+            .collect();
+        if !args.contains(&path) {
+            args.push(path);
+        }
+        return Some(args.join(" "));
+    }
+    // an editor for the terminal
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()?;
+    let terminal = std::env::var("TERMINAL").unwrap_or_else(|_| "cosmic-term".to_string());
+    Some(format!("{terminal} -e {editor} {path}"))
+}
+
+/// The desktop entries to show, sorted by name,
+/// along with the IDs of those which are only shown because hidden entries are shown,
+/// and the lowercased comments of the entries by ID.
+type LoadedEntries = (
+    Vec<Arc<DesktopEntryData>>,
+    HashSet<String>,
+    HashMap<String, String>,
+);
+
+fn load_entries(locale: Option<&str>, show_hidden: bool) -> LoadedEntries {
+    let xdg_current_desktop = std::env::var("XDG_CURRENT_DESKTOP").ok();
+    let locales: Vec<&str> = locale.into_iter().collect();
+    let hidden_entries = RefCell::new(HashSet::new());
+    let comments = RefCell::new(HashMap::new());
+    let generic_names = RefCell::new(HashMap::new());
+    let seen_ids = RefCell::new(HashSet::new());
+    let mut entries: Vec<_> = cosmic::desktop::load_applications_filtered(locale, |entry| {
+        if is_shadowed(&mut seen_ids.borrow_mut(), entry.id()) {
+            return false;
+        }
+        let hidden = entry.no_display() || entry.hidden();
+        if hidden && show_hidden {
+            hidden_entries.borrow_mut().insert(entry.id().to_string());
+        }
+        if let Some(comment) = entry.comment(&locales) {
+            comments
+                .borrow_mut()
+                .insert(entry.id().to_string(), comment.to_lowercase());
+        }
+        if entry
+            .name(&locales)
+            .map_or(true, |name| name.trim().is_empty())
+        {
+            if let Some(generic_name) = entry.generic_name(&locales) {
+                generic_names
+                    .borrow_mut()
+                    .insert(entry.id().to_string(), generic_name.to_string());
+            }
+        }
+        entry.exec().is_some()
+            && (show_hidden || !hidden)
+            && xdg_current_desktop
+                .as_ref()
+                .zip(entry.only_show_in())
+                .map(|(xdg_current_desktop, only_show_in)| {
+                    only_show_in.contains(xdg_current_desktop)
+                })
+                .unwrap_or(true)
+    })
+    .into_iter()
+    .map(|mut entry| {
+        // launchers without a name are shown by their generic name, or else their ID
+        if entry.name.trim().is_empty() {
+            entry.name = generic_names
+                .borrow()
+                .get(&entry.id)
+                .filter(|name| !name.trim().is_empty())
+                .cloned()
+                .unwrap_or_else(|| entry.id.clone());
+        }
+        Arc::new(entry)
+    })
+    .collect();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    (entries, hidden_entries.into_inner(), comments.into_inner())
+}
+
+/// Whether an entry with the ID was loaded already. Entries are loaded in order of precedence,
+/// so the first file with an ID, like a user's override of a system entry, shadows the rest,
+/// even when it hides the app.
+fn is_shadowed(seen_ids: &mut HashSet<String>, id: &str) -> bool {
+    !seen_ids.insert(id.to_string())
+}
+
+/// The ISO 639-1 codes of languages, which launchers for one language are suffixed with.
+const LANGUAGE_CODES: &[&str] = &[
+    "aa", "ab", "ae", "af", "ak", "am", "an", "ar", "as", "av", "ay", "az", "ba", "be", "bg", "bi",
+    "bm", "bn", "bo", "br", "bs", "ca", "ce", "ch", "co", "cr", "cs", "cu", "cv", "cy", "da", "de",
+    "dv", "dz", "ee", "el", "en", "eo", "es", "et", "eu", "fa", "ff", "fi", "fj", "fo", "fr", "fy",
+    "ga", "gd", "gl", "gn", "gu", "gv", "ha", "he", "hi", "ho", "hr", "ht", "hu", "hy", "hz", "ia",
+    "id", "ie", "ig", "ii", "ik", "io", "is", "it", "iu", "ja", "jv", "ka", "kg", "ki", "kj", "kk",
+    "kl", "km", "kn", "ko", "kr", "ks", "ku", "kv", "kw", "ky", "la", "lb", "lg", "li", "ln", "lo",
+    "lt", "lu", "lv", "mg", "mh", "mi", "mk", "ml", "mn", "mr", "ms", "mt", "my", "na", "nb", "nd",
+    "ne", "ng", "nl", "nn", "no", "nr", "nv", "ny", "oc", "oj", "om", "or", "os", "pa", "pi", "pl",
+    "ps", "pt", "qu", "rm", "rn", "ro", "ru", "rw", "sa", "sc", "sd", "se", "sg", "si", "sk", "sl",
+    "sm", "sn", "so", "sq", "sr", "ss", "st", "su", "sv", "sw", "ta", "te", "tg", "th", "ti", "tk",
+    "tl", "tn", "to", "tr", "ts", "tt", "tw", "ty", "ug", "uk", "ur", "uz", "ve", "vi", "vo", "wa",
+    "wo", "xh", "yi", "yo", "za", "zh", "zu",
+];
+
+/// Splits a desktop entry ID like `manual-fr` or `manual_pt_BR` into the app and its language.
+fn language_variant(id: &str) -> Option<(&str, &str)> {
+    let is_language = |s: &str| {
+        let (language, country) = s.split_once('_').unwrap_or((s, ""));
+        LANGUAGE_CODES.contains(&language)
+            && (country.is_empty()
+                || (country.len() == 2 && country.chars().all(|c| c.is_ascii_uppercase())))
+    };
+    id.char_indices()
+        .rev()
+        .filter(|(_, c)| *c == '-' || *c == '_')
+        .take(2)
+        .find_map(|(i, _)| {
+            let language = &id[i + 1..];
+            is_language(language).then(|| (&id[..i], language))
+        })
+}
+
+/// Keeps one of the launchers which only differ by a language suffix, when there are launchers
+/// for several languages: the one in the language of the session, or else the one without a suffix.
+fn session_language_variants(
+    entries: Vec<Arc<DesktopEntryData>>,
+    locale: Option<&str>,
+) -> Vec<Arc<DesktopEntryData>> {
+    let session_language = locale
+        .and_then(|locale| locale.split(['-', '_', '.']).next())
+        .map(str::to_lowercase);
+    // a language suffix on only one launcher is more likely a part of its name, like `bar-gl`
+    let mut suffixed: HashMap<&str, usize> = HashMap::new();
+    for entry in &entries {
+        if let Some((base, _)) = language_variant(&entry.id) {
+            *suffixed.entry(base).or_default() += 1;
+        }
+    }
+    let variant = |entry: &DesktopEntryData| {
+        language_variant(&entry.id)
+            .filter(|(base, _)| suffixed.get(base).is_some_and(|count| *count > 1))
+            .map(|(base, language)| (base.to_string(), language.to_string()))
+    };
+    let base_id =
+        |entry: &DesktopEntryData| variant(entry).map_or(entry.id.clone(), |(base, _)| base);
+    let rank = |entry: &DesktopEntryData| match variant(entry) {
+        Some((_, language)) => {
+            let language = language.split('_').next().unwrap_or(language.as_str());
+            if session_language.as_deref() == Some(language) {
+                0
+            } else {
+                2
+            }
+        }
+        None => 1,
+    };
+    let mut best: HashMap<String, &Arc<DesktopEntryData>> = HashMap::new();
+    for entry in &entries {
+        best.entry(base_id(entry))
+            .and_modify(|b| {
+                if rank(entry) < rank(*b) {
+                    *b = entry;
+                }
+            })
+            .or_insert(entry);
+    }
+    let keep: HashSet<String> = best.values().map(|entry| entry.id.clone()).collect();
+    entries
+        .into_iter()
+        .filter(|entry| keep.contains(&entry.id))
+        .collect()
+}
+
+/// The loaded entries which the library shows, along with the other variants of apps
+/// installed from several sources, by the path of the variant which is shown.
+fn shown_entries(
+    mut entries: Vec<Arc<DesktopEntryData>>,
+    config: &AppLibraryConfig,
+    locale: Option<&str>,
+) -> (
+    Vec<Arc<DesktopEntryData>>,
+    HashMap<PathBuf, Vec<Arc<DesktopEntryData>>>,
+) {
+    if config.session_language_only {
+        entries = session_language_variants(entries, locale);
+    }
+
+    // keep only the preferred variant of apps installed from several sources
+    let mut alternates = HashMap::new();
+    if !config.collapse_duplicates {
+        return (entries, alternates);
+    }
+    let order = &config.duplicate_source_order;
+    let rank = |e: &DesktopEntryData| {
+        let kind = e
+            .path
+            .as_ref()
+            .map(|path| AppSource::from(path.as_ref()).kind());
+        kind.and_then(|kind| order.iter().position(|o| o == kind))
+            .unwrap_or(order.len())
+    };
+    // variants share a name, and an ID once the reverse DNS prefix is dropped,
+    // like `firefox` and `org.mozilla.firefox`
+    let mut groups: Vec<Vec<Arc<DesktopEntryData>>> = Vec::new();
+    let mut group_by_key: HashMap<(String, String), usize> = HashMap::new();
+    for entry in entries {
+        let key = (entry.name.trim().to_lowercase(), normalized_id(&entry.id));
+        let group = *group_by_key.entry(key).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[group].push(entry);
+    }
+    let mut entries = Vec::with_capacity(groups.len());
+    for mut variants in groups {
+        variants.sort_by_key(|e| rank(e));
+        let preferred = variants.remove(0);
+        if let (Some(path), false) = (preferred.path.as_ref(), variants.is_empty()) {
+            alternates.insert(path.clone(), variants);
+        }
+        entries.push(preferred);
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    (entries, alternates)
+}
+
+/// How far the grid of apps can be scrolled down, so no empty space is shown below the apps.
+fn max_scroll_offset(apps: usize, row_height: f32) -> f32 {
+    let rows = apps.div_ceil(APPS_PER_ROW);
+    (rows as f32 * row_height - APP_GRID_MAX_HEIGHT).max(0.0)
+}
+
+/// The last component of a reverse DNS desktop entry ID, lowercased.
+fn normalized_id(id: &str) -> String {
+    id.rsplit('.').next().unwrap_or(id).to_lowercase()
+}
+
+/// Prints the apps of the library and the groups they are in as JSON.
+fn list_apps() {
+    let config = AppLibraryConfig::helper()
+        .map(|helper| AppLibraryConfig::get_entry(&helper).unwrap_or_else(|(_, config)| config))
+        .unwrap_or_default();
+    let state = AppLibraryState::helper()
+        .map(|helper| AppLibraryState::get_entry(&helper).unwrap_or_else(|(_, state)| state))
+        .unwrap_or_default();
+    let locale = current_locale::current_locale().ok();
+    let (entries, _, comments) =
+        load_entries(locale.as_deref(), config.show_hidden_desktop_entries);
+    let (entries, _) = shown_entries(entries, &config, locale.as_deref());
+
+    let groups = config.groups();
+    let members = (1..groups.len())
+        .filter(|i| !config.is_history(*i))
+        .map(|i| {
+            (
+                groups[i],
+                config.filtered(i, "", &entries, &comments, &state),
+            )
+        })
+        .collect_vec();
+    let apps = entries
+        .iter()
+        .map(|entry| {
+            let source = entry
+                .path
+                .as_ref()
+                .map(|path| AppSource::from(path.as_ref()));
+            serde_json::json!({
+                "id": entry.id,
+                "name": entry.name,
+                "source": source.as_ref().map(|s| s.kind()),
+                "source_details": source.as_ref().map(|s| s.description()),
+                "groups": members
+                    .iter()
+                    .filter(|(_, apps)| apps.iter().any(|app| app.id == entry.id))
+                    .map(|(group, _)| group.name())
+                    .collect_vec(),
+            })
+        })
+        .collect_vec();
+    match serde_json::to_string_pretty(&apps) {
+        Ok(json) => println!("{json}"),
+        Err(err) => error!("{:?}", err),
+    }
+}
+
 impl CosmicAppLibrary {
+    /// Requests an activation token, then launches the entry.
+    /// The entry is launched without a token if none arrives in time.
+    fn launch(&mut self, de: &DesktopEntryData, mut options: LaunchOptions) -> Task<Message> {
+        let launch = self.next_launch;
+        self.next_launch = self.next_launch.wrapping_add(1);
+        self.pending_launches.insert(launch);
+        let app_id = de.id.clone();
+        let exec = de.exec.clone().unwrap();
+        options.terminal = Some(options.terminal.unwrap_or(de.terminal));
+        let token = request_token(Some(String::from(Self::APP_ID)), Some(WINDOW_ID.clone())).map({
+            let app_id = app_id.clone();
+            let exec = exec.clone();
+            move |t| {
+                cosmic::app::Message::App(Message::ActivationToken(
+                    launch,
+                    t,
+                    app_id.clone(),
+                    exec.clone(),
+                    options,
+                ))
+            }
+        });
+        let timeout = Task::perform(tokio::time::sleep(ACTIVATION_TOKEN_TIMEOUT), move |_| {
+            cosmic::app::Message::App(Message::ActivationToken(
+                launch,
+                None,
+                app_id.clone(),
+                exec.clone(),
+                options,
+            ))
+        });
+        Task::batch(vec![token, timeout])
+    }
+
+    /// The GPU to launch an entry on, which is not the default one while Shift is held.
+    fn launch_gpu(&self, entry: &DesktopEntryData) -> Option<usize> {
+        let default = self.default_gpu(entry)?;
+        if self.modifiers.shift() {
+            let gpus = self.gpus.as_ref()?;
+            return (0..gpus.len()).find(|&i| i != default).or(Some(default));
+        }
+        Some(default)
+    }
+
+    /// The index of the GPU an entry runs on by default, if there are several.
+    fn default_gpu(&self, entry: &DesktopEntryData) -> Option<usize> {
+        let gpus = self.gpus.as_ref()?;
+        // the default GPU isn't necessarily the first one
+        let default = gpus.iter().position(|gpu| gpu.default).unwrap_or(0);
+        if entry.prefers_dgpu {
+            // the GPUs from switcheroo_control only tell which one is the default, not which
+            // are discrete, so with three or more GPUs, the first other one may not be discrete
+            return Some((0..gpus.len()).find(|&i| i != default).unwrap_or(default));
+        }
+        Some(default)
+    }
+
     pub fn activate(&mut self) -> Task<Message> {
         if self.active_surface {
-            return self.hide();
+            return self.fade_out();
         } else if !self
             .last_hide
             .is_some_and(|i| i.elapsed() < Duration::from_millis(100))
         {
             self.edit_name = None;
-            self.search_value = "".to_string();
+            if !self.config.remember_search {
+                self.search_value = "".to_string();
+            }
             self.active_surface = true;
+            self.search_only |= self.config.search_only;
             self.scroll_offset = 0.0;
+            self.scroll_relative = None;
+            self.overscroll = 0.0;
             self.cur_group = 0;
-            self.load_apps();
+            self.anim_progress = 0.0;
+            self.anim_start = Some(Instant::now());
+            self.hiding = false;
+            let load_apps = self.load_apps();
+            self.publish_status();
             let fetch_gpus = Task::perform(try_get_gpus(), |gpus| {
                 cosmic::app::Message::App(Message::GpuUpdate(gpus))
             });
@@ -271,6 +973,7 @@ impl CosmicAppLibrary {
                     size: Some((None, None)),
                     ..Default::default()
                 }),
+                load_apps,
                 fetch_gpus,
             ])
             .chain(text_input::focus(SEARCH_ID.clone()));
@@ -282,37 +985,105 @@ impl CosmicAppLibrary {
 #[derive(Clone, Debug)]
 enum Message {
     InputChanged(String),
+    ClearSearch,
+    PastePrimary,
+    MoveHighlight(isize),
+    ToggleGroupFocus,
+    Key(Key, Modifiers),
+    ToggleHelp,
+    ActivateHighlighted,
     Layer(LayerEvent, SurfaceId),
+    /// A popup was dismissed by the compositor, like when clicking outside of it.
+    PopupDone(SurfaceId),
+    Escape,
     Hide,
-    ActivateApp(usize, Option<usize>),
-    ActivationToken(Option<String>, String, String, Option<usize>),
+    Tick(Instant),
+    Touch(touch::Event),
+    PointerPressed,
+    Modifiers(Modifiers),
+    Resized(SurfaceId, f32),
+    ActivateApp(usize, LaunchOptions),
+    ActivateNewInstance(usize),
+    ActivateAlternate(usize, usize),
+    ActivationToken(u64, Option<String>, String, String, LaunchOptions),
     SelectGroup(usize),
-    Delete(usize),
+    CycleGroup(isize),
+    ToggleHome,
+    NavigateBack,
+    Delete(usize, Rectangle),
     ConfirmDelete,
     CancelDelete,
     StartEditName(String),
     EditName(String),
     SubmitName,
-    StartNewGroup,
+    StartEditGroupFilter(usize),
+    ToggleFilterCategory(String, bool),
+    ToggleFilterExcludedCategory(String, bool),
+    SetFilterMatchAll(bool),
+    RemoveFilterOverride(String),
+    SetEditorAccent(Option<String>),
+    SetEditorIcon(String),
+    SetEditorSort(SortOrder),
+    SubmitGroupEditor,
+    CancelEditGroupFilter,
+    LaunchEnvInput(String),
+    AddLaunchEnv,
+    RemoveLaunchEnv(usize),
+    SubmitLaunchOptions,
+    CancelLaunchOptions,
+    StartNewGroup(Rectangle),
     NewGroup(String),
     SubmitNewGroup,
     CancelNewGroup,
     LoadApps,
-    FilterApps(String, Vec<Arc<DesktopEntryData>>),
+    Refresh,
+    AppsLoaded(LoadedEntries),
+    FilterApps(String, Vec<Arc<DesktopEntryData>>, usize),
     OpenContextMenu(Rectangle, usize),
     CloseContextMenu,
+    OpenGroupMenu(Rectangle, usize),
+    CloseGroupMenu,
+    PinGroupToAppTray(usize, bool),
+    RenameGroup(usize),
+    OpenAll(usize, bool),
+    LaunchEntry(Arc<DesktopEntryData>),
+    EditGroupIcon(usize),
     SelectAction(MenuAction),
     StartDrag(usize),
     FinishDrag(bool),
     CancelDrag,
+    ReorderOffer(Option<usize>),
+    ReorderDrop(usize, Option<PathBuf>),
     StartDndOffer(usize),
     FinishDndOffer(usize, Option<DesktopEntryData>),
+    DropDesktopFile(Option<PathBuf>),
     LeaveDndOffer(usize),
-    ScrollYOffset(f32),
+    ScrollYOffset(f32, f32),
+    Wheel(mouse::ScrollDelta),
     GpuUpdate(Option<Vec<Gpu>>),
     PinToAppTray(usize),
+    PinToAppTrayStart(usize),
     UnPinFromAppTray(usize),
+    ToggleGroupMembership(usize, usize),
+    ClearHistory,
+    ToggleSelecting,
+    AddSelectionToGroup(usize),
+    RemoveSelection,
+    PinSelection,
+    ToggleFavorite(usize, bool),
+    OpenAppStore,
     AppListConfig(AppListConfig),
+    LibraryConfig(AppLibraryConfig),
+    Status(status::Event),
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct LaunchOptions {
+    gpu: Option<usize>,
+    /// Overrides whether the app is run in a terminal emulator.
+    terminal: Option<bool>,
+    /// Keeps the library open after launching, to launch more apps.
+    keep_open: bool,
 }
 
 #[derive(Clone)]
@@ -328,6 +1099,8 @@ impl Debug for DndCommand {
 enum MenuAction {
     Remove,
     DesktopAction(String),
+    EditDesktopFile,
+    LaunchOptions,
 }
 
 pub fn menu_button<'a, Message>(
@@ -339,6 +1112,69 @@ pub fn menu_button<'a, Message>(
         .width(Length::Fill)
 }
 
+/// Shortens a name longer than `max_len` characters, returning `None` if it fits.
+fn truncated_name(name: &str, max_len: usize) -> Option<String> {
+    (name.chars().count() > max_len).then(|| {
+        let name: String = name.chars().take(max_len.saturating_sub(3)).collect();
+        format!("{}...", name.trim_end())
+    })
+}
+
+/// A background for group tiles, so they read differently from app tiles.
+fn folder_style(mut style: button::Style, theme: &cosmic::Theme) -> button::Style {
+    if style.background.is_none() {
+        style.background = Some(Color::from(theme.cosmic().background.component.base).into());
+    }
+    style.border_radius = theme.cosmic().corner_radii.radius_m.into();
+    style
+}
+
+fn menu_style(theme: &cosmic::Theme) -> container::Style {
+    container::Style {
+        text_color: Some(theme.cosmic().on_bg_color().into()),
+        background: Some(Color::from(theme.cosmic().background.base).into()),
+        border: Border {
+            color: theme.cosmic().bg_divider().into(),
+            radius: theme.cosmic().corner_radii.radius_m.into(),
+            width: 1.0,
+        },
+        shadow: Shadow::default(),
+        icon_color: Some(theme.cosmic().on_bg_color().into()),
+    }
+}
+
+/// Opens a dialog in a popup below the button which opened it.
+fn dialog_popup(id: SurfaceId, rect: Rectangle) -> Task<Message> {
+    commands::popup::get_popup(SctkPopupSettings {
+        parent: WINDOW_ID.clone(),
+        id,
+        positioner: SctkPositioner {
+            size: None,
+            size_limits: Limits::NONE
+                .min_width(1.0)
+                .min_height(1.0)
+                .max_width(600.0)
+                .max_height(800.0),
+            anchor_rect: Rectangle {
+                x: rect.x as i32,
+                y: rect.y as i32,
+                width: rect.width as i32,
+                height: rect.height as i32,
+            },
+            anchor: sctk::reexports::protocols::xdg::shell::client::xdg_positioner::Anchor::Bottom,
+            gravity: sctk::reexports::protocols::xdg::shell::client::xdg_positioner::Gravity::Bottom,
+            // flip above buttons near the bottom edge
+            constraint_adjustment: (sctk::reexports::protocols::xdg::shell::client::xdg_positioner::ConstraintAdjustment::FlipY
+                | sctk::reexports::protocols::xdg::shell::client::xdg_positioner::ConstraintAdjustment::SlideX)
+                .bits(),
+            reactive: true,
+            ..Default::default()
+        },
+        grab: true,
+        parent_size: None,
+    })
+}
+
 pub fn menu_control_padding() -> Padding {
     let theme = cosmic::theme::active();
     let cosmic = theme.cosmic();
@@ -346,28 +1182,77 @@ pub fn menu_control_padding() -> Padding {
 }
 
 impl CosmicAppLibrary {
-    pub fn load_apps(&mut self) {
-        let locale = self.locale.as_deref();
-        let xdg_current_desktop = std::env::var("XDG_CURRENT_DESKTOP").ok();
-        self.all_entries = cosmic::desktop::load_applications_filtered(locale, |entry| {
-            entry.exec().is_some()
-                && !entry.no_display()
-                && xdg_current_desktop
-                    .as_ref()
-                    .zip(entry.only_show_in())
-                    .map(|(xdg_current_desktop, only_show_in)| {
-                        only_show_in.contains(xdg_current_desktop)
-                    })
-                    .unwrap_or(true)
-        })
-        .into_iter()
-        .map(Arc::new)
-        .collect();
-        self.all_entries.sort_by(|a, b| a.name.cmp(&b.name));
+    pub fn load_apps(&mut self) -> Task<Message> {
+        let entries = load_entries(
+            self.locale.as_deref(),
+            self.config.show_hidden_desktop_entries,
+        );
+        self.set_entries(entries)
+    }
 
-        self.entry_path_input =
-            self.config
-                .filtered(self.cur_group, &self.search_value, &self.all_entries);
+    /// Loads the apps in the background, for when the library is already shown.
+    fn reload_apps(&self) -> Task<Message> {
+        let locale = self.locale.clone();
+        let show_hidden = self.config.show_hidden_desktop_entries;
+        iced::Task::perform(
+            async move {
+                tokio::task::spawn_blocking(move || load_entries(locale.as_deref(), show_hidden))
+                    .await
+            },
+            |entries| match entries {
+                Ok(entries) => Message::AppsLoaded(entries),
+                Err(err) => {
+                    error!("{:?}", err);
+                    Message::LoadApps
+                }
+            },
+        )
+        .map(cosmic::app::Message::App)
+    }
+
+    /// Replaces the apps, returning a task which resolves their new icons in the background,
+    /// so tiles don't look them up while scrolling.
+    fn set_entries(&mut self, entries: LoadedEntries) -> Task<Message> {
+        (self.all_entries, self.hidden_entries, self.comments) = entries;
+
+        let icon_names = self
+            .all_entries
+            .iter()
+            .filter_map(|e| match &e.icon {
+                IconSource::Name(name) if !name.is_empty() && !Path::new(name).is_absolute() => {
+                    Some(name.clone())
+                }
+                _ => None,
+            })
+            .filter(|name| self.warmed_icons.insert(name.clone()))
+            .collect_vec();
+        let warm_icons = if icon_names.is_empty() {
+            Task::none()
+        } else {
+            iced::Task::perform(
+                tokio::task::spawn_blocking(move || {
+                    for name in icon_names {
+                        _ = named_app_icon(&name).path();
+                    }
+                }),
+                |_| (),
+            )
+            .discard()
+        };
+
+        (self.all_entries, self.alternates) = shown_entries(
+            std::mem::take(&mut self.all_entries),
+            &self.config,
+            self.locale.as_deref(),
+        );
+
+        self.entry_path_input = self.config.filtered(
+            self.cur_group,
+            &self.search_value,
+            &self.all_entries,
+            &self.comments,
+            &self.state,
+        );
 
         // collect duplicates
         self.duplicates.clear();
@@ -400,22 +1285,213 @@ impl CosmicAppLibrary {
                 },
             )
             .0;
+        if self.config.always_show_source {
+            for path in self.all_entries.iter().filter_map(|e| e.path.as_ref()) {
+                if !self.duplicates.contains_key(path) {
+                    self.duplicates
+                        .insert(path.clone(), AppSource::from(path.as_ref()));
+                }
+            }
+        }
+        warm_icons
+    }
+
+    /// The area around the library, which closes it when clicked if `click_outside_to_close` is set.
+    fn dismiss_area<'a>(&self, content: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+        let area = mouse_area(content);
+        if self.config.click_outside_to_close {
+            area.on_press(Message::Hide).into()
+        } else {
+            area.into()
+        }
+    }
+
+    fn publish_status(&self) {
+        if let Some(tx) = self.status_tx.as_ref() {
+            let group = self
+                .config
+                .groups()
+                .get(self.cur_group)
+                .map(|group| group.name())
+                .unwrap_or_default();
+            _ = tx.send(Status {
+                open: self.active_surface,
+                group,
+            });
+        }
+    }
+
+    /// The icon size, horizontal padding and width of the group tiles.
+    fn group_tile_size(&self) -> (f32, u16, f32) {
+        let cosmic::cosmic_theme::Spacing {
+            space_xxs, space_s, ..
+        } = theme::active().cosmic().spacing;
+        let compact = self
+            .config
+            .compact_groups
+            .unwrap_or(self.config.groups().len() > COMPACT_GROUPS_THRESHOLD);
+        if compact {
+            (16.0, space_xxs, 96.0)
+        } else {
+            (32.0, space_s, 128.0)
+        }
+    }
+
+    /// How many group tiles fit in a row of the window.
+    fn group_columns(&self) -> usize {
+        let cosmic::cosmic_theme::Spacing {
+            space_xxs,
+            space_xxl,
+            ..
+        } = theme::active().cosmic().spacing;
+        let available_width = if self.window_width > 0.0 {
+            self.window_width.min(MAX_WIDTH)
+        } else {
+            MAX_WIDTH
+        } - 2.0 * space_xxl as f32;
+        let (_, _, group_width) = self.group_tile_size();
+        ((available_width + space_xxs as f32) / (group_width + space_xxs as f32))
+            .floor()
+            .max(1.0) as usize
+    }
+
+    /// Changes the config, keeping the current group selected when groups come and go,
+    /// like the Favorites group with the favorites.
+    fn keep_cur_group(&mut self, f: impl FnOnce(&mut AppLibraryConfig)) {
+        let cur = self
+            .config
+            .groups()
+            .get(self.cur_group)
+            .map(|g| g.name.clone());
+        f(&mut self.config);
+        self.cur_group = cur
+            .and_then(|cur| self.config.groups().iter().position(|g| g.name == cur))
+            .unwrap_or(0);
+    }
+
+    /// Whether all apps of a group can be pinned at once. Library Home has every app,
+    /// and History changes with every launch.
+    fn can_pin_group(&self, group: usize) -> bool {
+        group > 0 && !self.config.is_history(group)
+    }
+
+    /// Pins the apps to the app tray, or unpins them.
+    fn set_pinned(&mut self, ids: &[String], pin: bool) {
+        let Ok(app_list_helper) =
+            Config::new(cosmic_app_list_config::APP_ID, AppListConfig::VERSION)
+        else {
+            return;
+        };
+        let mut favorites = self.app_list_config.favorites.clone();
+        if pin {
+            for id in ids {
+                if !favorites.contains(id) {
+                    favorites.push(id.clone());
+                }
+            }
+        } else {
+            favorites.retain(|id| !ids.contains(id));
+        }
+        if let Err(err) = self
+            .app_list_config
+            .set_favorites(&app_list_helper, favorites)
+        {
+            error!("{:?}", err);
+        }
+    }
+
+    /// Returns the config and the remembered state to their defaults, after backing them up.
+    fn reset(&mut self) -> Task<Message> {
+        AppLibraryConfig::backup();
+        AppLibraryState::backup();
+        self.state = AppLibraryState::default();
+        self.set_config(AppLibraryConfig::default());
+        if let Some(helper) = self.helper.as_ref() {
+            if let Err(err) = self.config.write_entry(helper) {
+                error!("{:?}", err);
+            }
+        }
+        if let Some(helper) = self.state_helper.as_ref() {
+            if let Err(err) = self.state.write_entry(helper) {
+                error!("{:?}", err);
+            }
+        }
+        self.cur_group = 0;
+        self.filter_apps()
+    }
+
+    /// Whether groups are being edited, which refer to them by their index.
+    fn editing_groups(&self) -> bool {
+        self.menu.is_some()
+            || self.group_menu.is_some()
+            || self.offer_group.is_some()
+            || self.new_group.is_some()
+            || self.edit_name.is_some()
+            || self.group_to_delete.is_some()
+            || self.group_editor.is_some()
+    }
+
+    /// Replaces the config with one changed outside of the library.
+    fn set_config(&mut self, mut config: AppLibraryConfig) {
+        config.sort_groups(&self.state);
+        self.key_binds = key_bind::key_binds(&config.key_binds);
+        self.keep_cur_group(|c| *c = config);
+    }
+
+    /// How far an app has to be dragged before it is moved, or None if apps can't be dragged.
+    fn drag_threshold(&self) -> Option<f32> {
+        if self.config.disable_drag_and_drop {
+            return None;
+        }
+        Some(self.config.drag_threshold.unwrap_or(if self.touch_input {
+            TOUCH_DRAG_THRESHOLD
+        } else {
+            DRAG_THRESHOLD
+        }))
+    }
+
+    /// The height of a row of apps, including the space below it.
+    fn app_row_height(&self) -> f32 {
+        let tile_height = if self.config.two_line_names {
+            APP_TILE_HEIGHT
+        } else {
+            APP_TILE_HEIGHT - APP_NAME_LINE_HEIGHT
+        };
+        tile_height + theme::active().cosmic().spacing.space_xxs as f32
+    }
+
+    /// The ID of the scrollable of the apps, which is kept per group.
+    fn app_grid_id(&self) -> Id {
+        Id::new(
+            self.config
+                .groups()
+                .get(self.cur_group)
+                .map(|g| g.name.clone())
+                .unwrap_or_else(|| "unknown-group".to_string()),
+        )
     }
 
     fn filter_apps(&mut self) -> Task<Message> {
         let config = self.config.clone();
         let all_entries = self.all_entries.clone();
+        let comments = self.comments.clone();
         let cur_group = self.cur_group;
         let input = self.search_value.clone();
+        let state = self.state.clone();
         if !self.waiting_for_filtered {
             self.waiting_for_filtered = true;
             iced::Task::perform(
                 async move {
-                    let mut apps = config.filtered(cur_group, &input, &all_entries);
-                    apps.sort_by(|a, b| a.name.cmp(&b.name));
-                    (input, apps)
+                    let mut apps =
+                        config.filtered(cur_group, &input, &all_entries, &comments, &state);
+                    let total = apps.len();
+                    // searches are ranked, so the best matches are kept
+                    if let Some(max) = config.max_results.filter(|_| !input.is_empty()) {
+                        apps.truncate(max);
+                    }
+                    (input, apps, total)
                 },
-                |(input, apps)| Message::FilterApps(input, apps),
+                |(input, apps, total)| Message::FilterApps(input, apps, total),
             )
             .map(cosmic::app::Message::App)
         } else {
@@ -423,6 +1499,15 @@ impl CosmicAppLibrary {
         }
     }
 
+    /// Fades out the library, hiding it once the animation has finished.
+    pub fn fade_out(&mut self) -> Task<Message> {
+        if !self.hiding {
+            self.hiding = true;
+            self.anim_start = Some(Instant::now());
+        }
+        Task::none()
+    }
+
     pub fn hide(&mut self) -> Task<Message> {
         // cancel existing dnd if it exists then try again...
         if self.dnd_icon.take().is_some() {
@@ -432,20 +1517,47 @@ impl CosmicAppLibrary {
             ]);
         }
         self.active_surface = false;
+        self.hiding = false;
+        self.anim_start = None;
         self.new_group = None;
-        self.search_value.clear();
+        // the search is only kept until the library is closed, unless it should be remembered
+        if !self.config.remember_search {
+            self.search_value.clear();
+        }
         self.edit_name = None;
         self.cur_group = 0;
         self.menu = None;
+        self.group_menu = None;
+        self.open_all_confirm = None;
+        self.highlighted = None;
+        self.highlighted_group = None;
         self.group_to_delete = None;
+        self.group_editor = None;
+        self.launch_options_editor = None;
+        self.help = false;
+        self.selecting = false;
+        self.selection.clear();
+        self.search_only = false;
+        if let Some(config) = self.pending_config.take() {
+            self.set_config(config);
+        }
         self.scroll_offset = 0.0;
+        self.scroll_relative = None;
+        self.overscroll = 0.0;
+        self.publish_status();
+        // the popups and dialogs go first, so that when the library goes the compositor gives
+        // focus back to the window which had it before, instead of to a dialog going away
         iced::Task::batch(vec![
             text_input::focus(SEARCH_ID.clone()),
             destroy_popup(MENU_ID.clone()),
-            destroy_layer_surface(NEW_GROUP_WINDOW_ID.clone()),
-            destroy_layer_surface(DELETE_GROUP_WINDOW_ID.clone()),
-            destroy_layer_surface(WINDOW_ID.clone()),
+            destroy_popup(GROUP_MENU_ID.clone()),
+            destroy_popup(NEW_GROUP_POPUP_ID.clone()),
+            destroy_popup(DELETE_GROUP_POPUP_ID.clone()),
+            destroy_layer_surface(EDIT_GROUP_FILTER_WINDOW_ID.clone()),
+            destroy_layer_surface(LAUNCH_OPTIONS_WINDOW_ID.clone()),
+            destroy_layer_surface(HELP_WINDOW_ID.clone()),
         ])
+        .chain(destroy_layer_surface(WINDOW_ID.clone()))
     }
 }
 
@@ -462,17 +1574,177 @@ impl cosmic::Application for CosmicAppLibrary {
     fn update(&mut self, message: Message) -> Task<Self::Message> {
         match message {
             Message::InputChanged(value) => {
+                // typing a space after a query with a single result launches it
+                if self.config.launch_unique_result
+                    && !self.waiting_for_filtered
+                    && !self.search_value.is_empty()
+                    && self.entry_path_input.len() == 1
+                    && value.strip_suffix(' ') == Some(self.search_value.as_str())
+                {
+                    return self.update(Message::ActivateHighlighted);
+                }
                 self.search_value = value;
+                self.highlighted = None;
+                self.highlighted_group = None;
                 return self.filter_apps();
             }
-            Message::Layer(e, id) => match e {
-                LayerEvent::Focused => {
+            Message::PastePrimary => {
+                // appended, as the cursor isn't moved by the middle click
+                let search = self.search_value.clone();
+                return Task::batch(vec![
+                    iced::clipboard::read_primary().map(move |text| {
+                        // a search is a single line
+                        let text = text.unwrap_or_default().lines().join(" ");
+                        cosmic::app::Message::App(Message::InputChanged(format!("{search}{text}")))
+                    }),
+                    text_input::focus(SEARCH_ID.clone()),
+                ]);
+            }
+            Message::ClearSearch => {
+                return Task::batch(vec![
+                    self.update(Message::InputChanged(String::new())),
+                    text_input::focus(SEARCH_ID.clone()),
+                ]);
+            }
+            Message::MoveHighlight(offset) => {
+                if self.menu.is_some() {
+                    return Task::none();
+                }
+                if let Some(group) = self.highlighted_group {
+                    let step = if offset.unsigned_abs() == APPS_PER_ROW {
+                        self.group_columns() as isize * offset.signum()
+                    } else {
+                        offset
+                    };
+                    let last = self.config.groups().len() - 1;
+                    self.highlighted_group = Some(group.saturating_add_signed(step).min(last));
+                    return Task::none();
+                }
+                let Some(last) = self.entry_path_input.len().checked_sub(1) else {
+                    return Task::none();
+                };
+                let Some(i) = self.highlighted else {
+                    self.highlighted = Some(0);
+                    return Task::none();
+                };
+                // moving past the first or last row
+                let row = i / APPS_PER_ROW;
+                let last_row = last / APPS_PER_ROW;
+                let vertical = offset.unsigned_abs() == APPS_PER_ROW;
+                if vertical && offset < 0 && row == 0 {
+                    self.highlighted = if self.config.nav_wrap {
+                        Some((last_row * APPS_PER_ROW + i).min(last))
+                    } else {
+                        // back to the search
+                        None
+                    };
+                    return Task::none();
+                }
+                if vertical && offset > 0 && row == last_row {
+                    if self.config.nav_wrap {
+                        self.highlighted = Some(i % APPS_PER_ROW);
+                    } else {
+                        self.highlighted = None;
+                        self.highlighted_group = Some(self.cur_group);
+                    }
+                    return Task::none();
+                }
+                self.highlighted = Some(i.saturating_add_signed(offset).min(last));
+            }
+            Message::Key(key, modifiers) => {
+                let Some(action) = key_bind::action(&self.key_binds, modifiers, &key) else {
+                    return Task::none();
+                };
+                let message = match action {
+                    Action::MoveLeft => Message::MoveHighlight(-1),
+                    Action::MoveRight => Message::MoveHighlight(1),
+                    Action::MoveUp => Message::MoveHighlight(-(APPS_PER_ROW as isize)),
+                    Action::MoveDown => Message::MoveHighlight(APPS_PER_ROW as isize),
+                    Action::ToggleGroupFocus => Message::ToggleGroupFocus,
+                    Action::NextGroup => Message::CycleGroup(1),
+                    Action::PreviousGroup => Message::CycleGroup(-1),
+                    Action::SelectGroup(group) => Message::SelectGroup(group),
+                    Action::ToggleHome => Message::ToggleHome,
+                    Action::Refresh => Message::Refresh,
+                    Action::ToggleHelp => Message::ToggleHelp,
+                };
+                return self.update(message);
+            }
+            Message::ToggleHelp => {
+                if self.help {
+                    self.help = false;
+                    return destroy_layer_surface(HELP_WINDOW_ID.clone());
+                }
+                self.help = true;
+                return get_layer_surface(SctkLayerSurfaceSettings {
+                    id: HELP_WINDOW_ID.clone(),
+                    keyboard_interactivity: KeyboardInteractivity::Exclusive,
+                    anchor: Anchor::empty(),
+                    namespace: "dialog".into(),
+                    size: None,
+                    ..Default::default()
+                });
+            }
+            Message::ToggleGroupFocus => {
+                self.highlighted_group = match self.highlighted_group {
+                    Some(_) => None,
+                    None => Some(self.cur_group),
+                };
+            }
+            Message::ActivateHighlighted => {
+                if let Some(group) = self.highlighted_group {
+                    return self.update(Message::SelectGroup(group));
+                }
+                let Some(i) = self.highlighted.or_else(|| {
+                    (!self.search_value.is_empty() && !self.entry_path_input.is_empty())
+                        .then_some(0)
+                }) else {
+                    return Task::none();
+                };
+                let gpu = self
+                    .entry_path_input
+                    .get(i)
+                    .and_then(|entry| self.launch_gpu(entry));
+                // Ctrl+Enter keeps the library open, ready for the next search
+                let keep_open = self.modifiers.control();
+                let launch = self.update(Message::ActivateApp(
+                    i,
+                    LaunchOptions {
+                        gpu,
+                        keep_open,
+                        ..Default::default()
+                    },
+                ));
+                if keep_open {
+                    return Task::batch(vec![launch, self.update(Message::ClearSearch)]);
+                }
+                return launch;
+            }
+            Message::PopupDone(id) => {
+                if id == MENU_ID.clone() {
+                    self.menu = None;
+                } else if id == GROUP_MENU_ID.clone() {
+                    self.group_menu = None;
+                    self.open_all_confirm = None;
+                } else if id == NEW_GROUP_POPUP_ID.clone() {
+                    self.new_group = None;
+                } else if id == DELETE_GROUP_POPUP_ID.clone() {
+                    self.group_to_delete = None;
+                }
+                // changes to the config were held back while the popup was open
+                if !self.editing_groups() {
+                    if let Some(config) = self.pending_config.take() {
+                        self.set_config(config);
+                        return self.filter_apps();
+                    }
+                }
+            }
+            Message::Layer(e, id) => match e {
+                LayerEvent::Focused => {
                     if id == WINDOW_ID.clone() {
                         return text_input::focus(SEARCH_ID.clone());
-                    } else if id == DELETE_GROUP_WINDOW_ID.clone() {
-                        return button::focus(SUBMIT_DELETE_ID.clone());
-                    } else if id == NEW_GROUP_WINDOW_ID.clone() {
-                        return text_input::focus(NEW_GROUP_ID.clone());
+                    } else if id == LAUNCH_OPTIONS_WINDOW_ID.clone() {
+                        return text_input::focus(LAUNCH_ENV_ID.clone());
                     }
                 }
                 LayerEvent::Unfocused => {
@@ -482,6 +1754,9 @@ impl cosmic::Application for CosmicAppLibrary {
                         && self.menu.is_none()
                         && self.new_group.is_none()
                         && self.group_to_delete.is_none()
+                        && self.group_editor.is_none()
+                        && self.launch_options_editor.is_none()
+                        && !self.help
                     {
                         return self.hide();
                     }
@@ -492,67 +1767,238 @@ impl cosmic::Application for CosmicAppLibrary {
                 }
                 _ => {}
             },
+            Message::Escape => {
+                // close whatever is on top, and the library only when nothing else is open
+                let message = if self.menu.is_some() {
+                    Message::CloseContextMenu
+                } else if self.new_group.is_some() {
+                    Message::CancelNewGroup
+                } else if self.group_to_delete.is_some() {
+                    Message::CancelDelete
+                } else if self.group_editor.is_some() {
+                    Message::CancelEditGroupFilter
+                } else if self.launch_options_editor.is_some() {
+                    Message::CancelLaunchOptions
+                } else if self.help {
+                    Message::ToggleHelp
+                } else if self.selecting {
+                    Message::ToggleSelecting
+                } else if self.edit_name.is_some() {
+                    self.edit_name = None;
+                    return Task::none();
+                } else {
+                    Message::Hide
+                };
+                return self.update(message);
+            }
             Message::Hide => {
+                if self.active_surface && self.dnd_icon.is_none() {
+                    return self.fade_out();
+                }
                 return self.hide();
             }
-            Message::ActivateApp(i, gpu_idx) => {
+            Message::PointerPressed => {
+                self.touch_input = false;
+            }
+            Message::Touch(event) => {
+                if matches!(event, touch::Event::FingerPressed { .. }) {
+                    self.touch_input = true;
+                }
+            }
+            Message::Modifiers(modifiers) => {
+                self.modifiers = modifiers;
+            }
+            Message::Resized(id, width) => {
+                if id == *WINDOW_ID {
+                    self.window_width = width;
+                }
+            }
+            Message::Tick(now) => {
+                if let Some(start) = self.anim_start {
+                    let t = (now.saturating_duration_since(start).as_secs_f32()
+                        / ANIMATION_DURATION.as_secs_f32())
+                    .min(1.0);
+                    self.anim_progress = if self.hiding { 1.0 - t } else { t };
+                    if t >= 1.0 {
+                        self.anim_start = None;
+                        if self.hiding {
+                            return self.hide();
+                        }
+                    }
+                }
+            }
+            Message::ActivateApp(i, mut options) => {
                 self.edit_name = None;
-                if let Some(de) = self.entry_path_input.get(i) {
-                    let app_id = de.id.clone();
-                    let exec = de.exec.clone().unwrap();
-                    return request_token(
-                        Some(String::from(Self::APP_ID)),
-                        Some(WINDOW_ID.clone()),
-                    )
-                    .map(move |t| {
-                        cosmic::app::Message::App(Message::ActivationToken(
-                            t,
-                            app_id.clone(),
-                            exec.clone(),
-                            gpu_idx,
-                        ))
-                    });
+                if self.selecting {
+                    if let Some(id) = self.entry_path_input.get(i).map(|e| e.id.clone()) {
+                        if !self.selection.remove(&id) {
+                            self.selection.insert(id);
+                        }
+                    }
+                    return Task::none();
+                }
+                // Shift already picks the other GPU
+                options.keep_open |= self.modifiers.control();
+                if let Some(de) = self.entry_path_input.get(i).cloned() {
+                    return self.launch(&de, options);
+                }
+            }
+            Message::ActivateNewInstance(i) => {
+                self.edit_name = None;
+                let Some(de) = self.entry_path_input.get(i).cloned() else {
+                    return Task::none();
+                };
+                let options = LaunchOptions {
+                    gpu: self.launch_gpu(&de),
+                    ..Default::default()
+                };
+                // apps which can open another window usually offer it as the new-window action
+                let new_window = de
+                    .path
+                    .as_ref()
+                    .and_then(|path| DesktopEntry::from_path::<&str>(path, None).ok())
+                    .and_then(|entry| entry.action_exec("new-window").map(str::to_string));
+                let Some(exec) = new_window else {
+                    return self.launch(&de, options);
+                };
+                let de = DesktopEntryData {
+                    exec: Some(exec),
+                    ..(*de).clone()
+                };
+                return self.launch(&de, options);
+            }
+            Message::ActivateAlternate(i, j) => {
+                self.edit_name = None;
+                let alternate = self
+                    .entry_path_input
+                    .get(i)
+                    .and_then(|de| de.path.as_ref())
+                    .and_then(|path| self.alternates.get(path))
+                    .and_then(|alternates| alternates.get(j))
+                    .cloned();
+                if let Some(de) = alternate {
+                    let options = LaunchOptions {
+                        gpu: self.default_gpu(&de),
+                        ..Default::default()
+                    };
+                    return self.launch(&de, options);
                 }
             }
-            Message::ActivationToken(token, app_id, exec, gpu_idx) => {
+            Message::ActivationToken(launch, token, app_id, mut exec, options) => {
+                // whichever of the token and the timeout comes first launches the app
+                if !self.pending_launches.remove(&launch) {
+                    return Task::none();
+                }
                 let mut env_vars = Vec::new();
                 if let Some(token) = token {
                     env_vars.push(("XDG_ACTIVATION_TOKEN".to_string(), token.clone()));
                     env_vars.push(("DESKTOP_STARTUP_ID".to_string(), token));
                 }
-                if let (Some(gpus), Some(idx)) = (self.gpus.as_ref(), gpu_idx) {
+                if let (Some(gpus), Some(idx)) = (self.gpus.as_ref(), options.gpu) {
                     env_vars.extend(gpus[idx].environment.clone().into_iter());
                 }
+                if let Some(env) = self.config.launch_env.get(&app_id) {
+                    env_vars.extend(env.iter().cloned());
+                }
+                if options.terminal == Some(true) {
+                    let terminal =
+                        std::env::var("TERMINAL").unwrap_or_else(|_| "cosmic-term".to_string());
+                    exec = format!("{terminal} -e {exec}");
+                }
+                self.state.record_launch(&app_id);
+                self.state.add_search(&self.search_value);
+                let group_used = self
+                    .all_entries
+                    .iter()
+                    .find(|e| e.id == app_id)
+                    .is_some_and(|entry| self.state.record_group_use(&self.config.groups, entry));
+                if let Some(helper) = self.state_helper.as_ref() {
+                    if let Err(err) = self.state.write_entry(helper) {
+                        error!("{:?}", err);
+                    }
+                }
+                if group_used && self.config.group_order == GroupOrder::RecentlyUsed {
+                    let cur_group = self.config.groups()[self.cur_group].clone();
+                    self.config.sort_groups(&self.state);
+                    // the library may stay open, showing the same group at its new position
+                    if let Some(i) = self
+                        .config
+                        .groups()
+                        .iter()
+                        .position(|g| g.name == cur_group.name && g.filter == cur_group.filter)
+                    {
+                        self.cur_group = i;
+                    }
+                }
                 tokio::spawn(async move {
                     cosmic::desktop::spawn_desktop_exec(exec, env_vars, Some(&app_id)).await
                 });
+                if options.keep_open {
+                    return Task::none();
+                }
                 return self.update(Message::Hide);
             }
             Message::SelectGroup(i) => {
+                if i >= self.config.groups().len() {
+                    return Task::none();
+                }
                 self.edit_name = None;
+                self.highlighted = None;
                 self.search_value.clear();
+                if i != self.cur_group {
+                    self.previous_group = self.cur_group;
+                }
                 self.cur_group = i;
                 self.scroll_offset = 0.0;
+                self.scroll_relative = None;
+                self.overscroll = 0.0;
+                self.publish_status();
                 let mut cmds = vec![self.filter_apps()];
                 if self.cur_group == 0 {
                     cmds.push(text_input::focus(SEARCH_ID.clone()));
                 }
                 return iced::Task::batch(cmds);
             }
+            Message::CycleGroup(offset) => {
+                let len = self.config.groups().len() as isize;
+                let i = (self.cur_group as isize + offset).rem_euclid(len);
+                return self.update(Message::SelectGroup(i as usize));
+            }
+            Message::ToggleHome => {
+                let group = if self.cur_group == 0 {
+                    self.previous_group
+                } else {
+                    0
+                };
+                return self.update(Message::SelectGroup(group));
+            }
+            Message::NavigateBack => {
+                if self.menu.is_some() {
+                    return self.update(Message::CloseContextMenu);
+                }
+                if self.cur_group != 0 {
+                    return self.update(Message::SelectGroup(0));
+                }
+                return self.update(Message::Hide);
+            }
             Message::LoadApps => {
                 return self.filter_apps();
             }
-            Message::Delete(group) => {
+            Message::Refresh => {
+                return self.reload_apps();
+            }
+            Message::AppsLoaded(entries) => {
+                self.reload_highlight = self
+                    .highlighted
+                    .and_then(|i| self.entry_path_input.get(i))
+                    .map(|entry| entry.id.clone());
+                return Task::batch(vec![self.set_entries(entries), self.filter_apps()]);
+            }
+            Message::Delete(group, rect) => {
                 self.group_to_delete = Some(group);
                 return Task::batch(vec![
-                    get_layer_surface(SctkLayerSurfaceSettings {
-                        id: DELETE_GROUP_WINDOW_ID.clone(),
-                        keyboard_interactivity: KeyboardInteractivity::Exclusive,
-                        anchor: Anchor::empty(),
-                        namespace: "dialog".into(),
-                        size: None,
-                        ..Default::default()
-                    }),
+                    self.update(Message::CloseGroupMenu),
+                    dialog_popup(DELETE_GROUP_POPUP_ID.clone(), rect),
                     button::focus(SUBMIT_DELETE_ID.clone()),
                 ]);
             }
@@ -569,21 +2015,185 @@ impl cosmic::Application for CosmicAppLibrary {
                     }
                 }
             }
-            Message::StartEditName(name) => {
-                self.edit_name = Some(name);
-                return text_input::focus(EDIT_GROUP_ID.clone());
-            }
-            Message::StartNewGroup => {
-                self.new_group = Some(String::new());
+            Message::StartEditGroupFilter(group) => {
+                let Some(filter) = self.config.filter(group) else {
+                    return Task::none();
+                };
+                let filter = match filter {
+                    FilterType::AppIds(ids) => FilterType::Categories {
+                        categories: Vec::new(),
+                        exclude: Vec::new(),
+                        include: ids.clone(),
+                        match_all: false,
+                        exclude_categories: Vec::new(),
+                    },
+                    filter => filter.clone(),
+                };
+                let (accent, icon, sort) = self
+                    .config
+                    .groups()
+                    .get(group)
+                    .map(|g| (g.accent.clone(), g.icon.clone(), g.sort))
+                    .unwrap_or_default();
+                self.group_editor = Some(GroupEditor {
+                    group,
+                    filter,
+                    accent,
+                    icon,
+                    sort,
+                });
                 return Task::batch(vec![
+                    self.update(Message::CloseGroupMenu),
                     get_layer_surface(SctkLayerSurfaceSettings {
-                        id: NEW_GROUP_WINDOW_ID.clone(),
+                        id: EDIT_GROUP_FILTER_WINDOW_ID.clone(),
                         keyboard_interactivity: KeyboardInteractivity::Exclusive,
                         anchor: Anchor::empty(),
                         namespace: "dialog".into(),
                         size: None,
                         ..Default::default()
                     }),
+                ]);
+            }
+            Message::ToggleFilterCategory(category, enabled) => {
+                if let Some(FilterType::Categories {
+                    categories,
+                    exclude_categories,
+                    ..
+                }) = self.group_editor.as_mut().map(|e| &mut e.filter)
+                {
+                    categories.retain(|c| c != &category);
+                    if enabled {
+                        exclude_categories.retain(|c| c != &category);
+                        categories.push(category);
+                    }
+                }
+            }
+            Message::ToggleFilterExcludedCategory(category, enabled) => {
+                if let Some(FilterType::Categories {
+                    categories,
+                    exclude_categories,
+                    ..
+                }) = self.group_editor.as_mut().map(|e| &mut e.filter)
+                {
+                    exclude_categories.retain(|c| c != &category);
+                    if enabled {
+                        categories.retain(|c| c != &category);
+                        exclude_categories.push(category);
+                    }
+                }
+            }
+            Message::SetFilterMatchAll(value) => {
+                if let Some(FilterType::Categories { match_all, .. }) =
+                    self.group_editor.as_mut().map(|e| &mut e.filter)
+                {
+                    *match_all = value;
+                }
+            }
+            Message::RemoveFilterOverride(id) => {
+                if let Some(FilterType::Categories {
+                    include, exclude, ..
+                }) = self.group_editor.as_mut().map(|e| &mut e.filter)
+                {
+                    include.retain(|i| i != &id);
+                    exclude.retain(|i| i != &id);
+                }
+            }
+            Message::SetEditorAccent(accent) => {
+                if let Some(editor) = self.group_editor.as_mut() {
+                    editor.accent = accent;
+                }
+            }
+            Message::SetEditorIcon(icon) => {
+                if let Some(editor) = self.group_editor.as_mut() {
+                    editor.icon = icon;
+                }
+            }
+            Message::SetEditorSort(sort) => {
+                if let Some(editor) = self.group_editor.as_mut() {
+                    editor.sort = sort;
+                }
+            }
+            Message::SubmitGroupEditor => {
+                let Some(GroupEditor {
+                    group,
+                    filter,
+                    accent,
+                    icon,
+                    sort,
+                }) = self.group_editor.take()
+                else {
+                    return Task::none();
+                };
+                self.config.set_accent(group, accent);
+                if !icon.trim().is_empty() {
+                    self.config.set_icon(group, icon.trim().to_string());
+                }
+                self.config.set_sort(group, sort);
+                if let Some(new_index) = self.config.set_filter(group, filter, &self.state) {
+                    if self.cur_group == group {
+                        self.cur_group = new_index;
+                    }
+                }
+                if let Some(helper) = self.helper.as_ref() {
+                    if let Err(err) = self.config.write_entry(helper) {
+                        error!("{:?}", err);
+                    }
+                }
+                return Task::batch(vec![
+                    destroy_layer_surface(EDIT_GROUP_FILTER_WINDOW_ID.clone()),
+                    self.filter_apps(),
+                ]);
+            }
+            Message::CancelEditGroupFilter => {
+                self.group_editor = None;
+                return destroy_layer_surface(EDIT_GROUP_FILTER_WINDOW_ID.clone());
+            }
+            Message::LaunchEnvInput(input) => {
+                if let Some(editor) = self.launch_options_editor.as_mut() {
+                    editor.input = input;
+                }
+            }
+            Message::AddLaunchEnv => {
+                if let Some(editor) = self.launch_options_editor.as_mut() {
+                    editor.add_input();
+                }
+            }
+            Message::RemoveLaunchEnv(i) => {
+                if let Some(editor) = self.launch_options_editor.as_mut() {
+                    if i < editor.env.len() {
+                        editor.env.remove(i);
+                    }
+                }
+            }
+            Message::SubmitLaunchOptions => {
+                if let Some(mut editor) = self.launch_options_editor.take() {
+                    // a variable typed but not added yet is saved as well
+                    editor.add_input();
+                    if editor.env.is_empty() {
+                        self.config.launch_env.remove(&editor.app_id);
+                    } else {
+                        self.config.launch_env.insert(editor.app_id, editor.env);
+                    }
+                    if let Some(helper) = self.helper.as_ref() {
+                        if let Err(err) = self.config.write_entry(helper) {
+                            error!("{:?}", err);
+                        }
+                    }
+                }
+                return destroy_layer_surface(LAUNCH_OPTIONS_WINDOW_ID.clone());
+            }
+            Message::CancelLaunchOptions => {
+                self.launch_options_editor = None;
+                return destroy_layer_surface(LAUNCH_OPTIONS_WINDOW_ID.clone());
+            }
+            Message::StartEditName(name) => {
+                self.edit_name = Some(name);
+                return text_input::focus(EDIT_GROUP_ID.clone());
+            }
+            Message::StartNewGroup(rect) => {
+                self.new_group = Some(String::new());
+                return Task::batch(vec![
+                    dialog_popup(NEW_GROUP_POPUP_ID.clone(), rect),
                     text_input::focus(NEW_GROUP_ID.clone()),
                 ]);
             }
@@ -592,24 +2202,44 @@ impl cosmic::Application for CosmicAppLibrary {
             }
             Message::SubmitNewGroup => {
                 if let Some(group_name) = self.new_group.take() {
-                    self.config.add(group_name);
+                    self.config.add(group_name.clone(), &self.state);
+                    // the selected apps go into a folder created while selecting
+                    if self.selecting && !self.selection.is_empty() {
+                        let group = self
+                            .config
+                            .groups()
+                            .iter()
+                            .rposition(|g| g.name == group_name);
+                        if let Some(group) = group {
+                            for id in std::mem::take(&mut self.selection) {
+                                self.config.add_entry(group, &id);
+                            }
+                            self.selecting = false;
+                        }
+                    }
                 }
                 if let Some(helper) = self.helper.as_ref() {
                     if let Err(err) = self.config.write_entry(helper) {
                         error!("{:?}", err);
                     }
                 }
-                return destroy_layer_surface(NEW_GROUP_WINDOW_ID.clone());
+                return destroy_popup(NEW_GROUP_POPUP_ID.clone());
             }
             Message::CancelNewGroup => {
                 self.new_group = None;
-                return destroy_layer_surface(NEW_GROUP_WINDOW_ID.clone());
+                return destroy_popup(NEW_GROUP_POPUP_ID.clone());
             }
             Message::OpenContextMenu(rect, i) => {
-                if self.menu.take().is_some() {
+                if let Some(i) = self.menu.take() {
+                    self.highlighted = Some(i);
                     return destroy_popup(MENU_ID.clone());
                 } else {
                     self.menu = Some(i);
+                    self.menu_info = self
+                        .entry_path_input
+                        .get(i)
+                        .map(|entry| ContextMenuInfo::new(entry))
+                        .unwrap_or_default();
                     return commands::popup::get_popup(SctkPopupSettings {
                         parent: WINDOW_ID.clone(),
                         id: MENU_ID.clone(),
@@ -625,6 +2255,10 @@ impl cosmic::Application for CosmicAppLibrary {
                             anchor:
                                 sctk::reexports::protocols::xdg::shell::client::xdg_positioner::Anchor::Right,
                             gravity: sctk::reexports::protocols::xdg::shell::client::xdg_positioner::Gravity::Right,
+                            // flip to the left of tiles near the right edge
+                            constraint_adjustment: (sctk::reexports::protocols::xdg::shell::client::xdg_positioner::ConstraintAdjustment::FlipX
+                                | sctk::reexports::protocols::xdg::shell::client::xdg_positioner::ConstraintAdjustment::SlideY)
+                                .bits(),
                             reactive: true,
                             ..Default::default()
                         },
@@ -634,9 +2268,109 @@ impl cosmic::Application for CosmicAppLibrary {
                 }
             }
             Message::CloseContextMenu => {
-                self.menu = None;
+                // keyboard navigation continues from the app the menu was opened for
+                if let Some(i) = self.menu.take() {
+                    self.highlighted = Some(i);
+                }
                 return commands::popup::destroy_popup(MENU_ID.clone());
             }
+            Message::OpenGroupMenu(rect, group) => {
+                self.open_all_confirm = None;
+                if self.group_menu.take().is_some() {
+                    return destroy_popup(GROUP_MENU_ID.clone());
+                }
+                self.group_menu = Some(group);
+                self.group_menu_bounds = rect;
+                return commands::popup::get_popup(SctkPopupSettings {
+                    parent: WINDOW_ID.clone(),
+                    id: GROUP_MENU_ID.clone(),
+                    positioner: SctkPositioner {
+                        size: None,
+                        size_limits: Limits::NONE
+                            .min_width(1.0)
+                            .min_height(1.0)
+                            .max_width(300.0)
+                            .max_height(800.0),
+                        anchor_rect: Rectangle {
+                            x: rect.x as i32,
+                            y: rect.y as i32,
+                            width: rect.width as i32,
+                            height: rect.height as i32,
+                        },
+                        anchor: sctk::reexports::protocols::xdg::shell::client::xdg_positioner::Anchor::Top,
+                        gravity: sctk::reexports::protocols::xdg::shell::client::xdg_positioner::Gravity::Top,
+                        // flip below tiles near the top edge
+                        constraint_adjustment: (sctk::reexports::protocols::xdg::shell::client::xdg_positioner::ConstraintAdjustment::FlipY
+                            | sctk::reexports::protocols::xdg::shell::client::xdg_positioner::ConstraintAdjustment::SlideX)
+                            .bits(),
+                        reactive: true,
+                        ..Default::default()
+                    },
+                    grab: true,
+                    parent_size: None,
+                });
+            }
+            Message::CloseGroupMenu => {
+                self.open_all_confirm = None;
+                if self.group_menu.take().is_some() {
+                    return destroy_popup(GROUP_MENU_ID.clone());
+                }
+            }
+            Message::OpenAll(group, confirmed) => {
+                let apps =
+                    self.config
+                        .filtered(group, "", &self.all_entries, &self.comments, &self.state);
+                if !confirmed && apps.len() > OPEN_ALL_CONFIRM_COUNT {
+                    self.open_all_confirm = Some(apps.len());
+                    return Task::none();
+                }
+                let mut tasks = vec![self.update(Message::CloseGroupMenu)];
+                // each app gets its own activation token, a little after the previous one
+                for (n, de) in apps.into_iter().enumerate() {
+                    tasks.push(Task::perform(
+                        tokio::time::sleep(OPEN_ALL_STAGGER * n as u32),
+                        move |_| cosmic::app::Message::App(Message::LaunchEntry(de.clone())),
+                    ));
+                }
+                return Task::batch(tasks);
+            }
+            Message::LaunchEntry(de) => {
+                let options = LaunchOptions {
+                    gpu: self.default_gpu(&de),
+                    ..Default::default()
+                };
+                return self.launch(&de, options);
+            }
+            Message::RenameGroup(group) => {
+                // the name is edited in the header of the group
+                let Some(name) = self.config.groups().get(group).map(|g| g.name()) else {
+                    return Task::none();
+                };
+                return Task::batch(vec![
+                    self.update(Message::CloseGroupMenu),
+                    self.update(Message::SelectGroup(group)),
+                    self.update(Message::StartEditName(name)),
+                ]);
+            }
+            Message::EditGroupIcon(group) => {
+                return Task::batch(vec![
+                    self.update(Message::StartEditGroupFilter(group)),
+                    text_input::focus(EDIT_GROUP_ICON_ID.clone()),
+                ]);
+            }
+            Message::PinGroupToAppTray(group, pin) => {
+                if !self.can_pin_group(group) {
+                    return Task::none();
+                }
+                let ids = self
+                    .config
+                    .filtered(group, "", &self.all_entries, &self.comments, &self.state)
+                    .into_iter()
+                    .map(|e| e.id.clone())
+                    .collect_vec();
+                self.set_pinned(&ids, pin);
+                return self.update(Message::CloseGroupMenu);
+            }
             Message::SelectAction(action) => {
                 if let Some(info) = self.menu.take().and_then(|i| self.entry_path_input.get(i)) {
                     match action {
@@ -667,6 +2401,48 @@ impl cosmic::Application for CosmicAppLibrary {
                             let _ = cmd.spawn();
                             return self.hide();
                         }
+                        MenuAction::LaunchOptions => {
+                            self.launch_options_editor = Some(LaunchOptionsEditor {
+                                app_id: info.id.clone(),
+                                app_name: info.name.clone(),
+                                env: self
+                                    .config
+                                    .launch_env
+                                    .get(&info.id)
+                                    .cloned()
+                                    .unwrap_or_default(),
+                                input: String::new(),
+                            });
+                            return Task::batch(vec![
+                                destroy_popup(MENU_ID.clone()),
+                                get_layer_surface(SctkLayerSurfaceSettings {
+                                    id: LAUNCH_OPTIONS_WINDOW_ID.clone(),
+                                    keyboard_interactivity: KeyboardInteractivity::Exclusive,
+                                    anchor: Anchor::empty(),
+                                    namespace: "dialog".into(),
+                                    size: None,
+                                    ..Default::default()
+                                }),
+                                text_input::focus(LAUNCH_ENV_ID.clone()),
+                            ]);
+                        }
+                        MenuAction::EditDesktopFile => {
+                            if let Some(path) = info.path.clone() {
+                                tokio::spawn(async move {
+                                    let Some(exec) = text_editor_exec(&path).await else {
+                                        error!("no text editor to open {}", path.display());
+                                        return;
+                                    };
+                                    cosmic::desktop::spawn_desktop_exec(
+                                        exec,
+                                        Vec::<(String, String)>::new(),
+                                        None,
+                                    )
+                                    .await
+                                });
+                            }
+                            return self.hide();
+                        }
                     }
                 }
             }
@@ -674,13 +2450,19 @@ impl cosmic::Application for CosmicAppLibrary {
                 self.dnd_icon = Some(i);
             }
             Message::FinishDrag(copy) => {
+                // an app moved within the group stays in it
+                if std::mem::take(&mut self.reordered) {
+                    self.dnd_icon = None;
+                    return Task::none();
+                }
                 if !copy {
                     if let Some(info) = self
                         .dnd_icon
                         .take()
                         .and_then(|i| self.entry_path_input.get(i))
                     {
-                        self.config.remove_entry(self.cur_group, &info.id);
+                        let (cur_group, id) = (self.cur_group, info.id.clone());
+                        self.keep_cur_group(|config| config.remove_entry(cur_group, &id));
                         if let Some(helper) = self.helper.as_ref() {
                             if let Err(err) = self.config.write_entry(helper) {
                                 error!("{:?}", err);
@@ -692,16 +2474,63 @@ impl cosmic::Application for CosmicAppLibrary {
             }
             Message::CancelDrag => {
                 self.dnd_icon = None;
+                self.reorder_target = None;
+            }
+            Message::ReorderOffer(target) => {
+                self.reorder_target = target;
+            }
+            Message::ReorderDrop(target, path) => {
+                self.reorder_target = None;
+                let Some(from) = path.as_ref().and_then(|path| {
+                    self.entry_path_input
+                        .iter()
+                        .position(|e| e.path.as_ref() == Some(path))
+                }) else {
+                    // dropped from outside the group, e.g. from a file manager
+                    return self.update(Message::DropDesktopFile(path));
+                };
+                self.reordered = true;
+                let mut order = self
+                    .entry_path_input
+                    .iter()
+                    .map(|e| e.id.clone())
+                    .collect_vec();
+                let id = order.remove(from);
+                let to = if from < target { target - 1 } else { target };
+                order.insert(to.min(order.len()), id);
+                self.config.set_order(self.cur_group, order);
+                if let Some(helper) = self.helper.as_ref() {
+                    if let Err(err) = self.config.write_entry(helper) {
+                        error!("{:?}", err);
+                    }
+                }
+                return self.filter_apps();
             }
 
             Message::StartDndOffer(i) => {
                 self.offer_group = Some(i);
+                // only the apps dragged from the library are known before they are dropped
+                self.offer_contains = self
+                    .dnd_icon
+                    .and_then(|dragged| self.entry_path_input.get(dragged))
+                    .is_some_and(|entry| self.config.contains(i, entry));
             }
             Message::FinishDndOffer(i, entry) => {
                 self.offer_group = None;
                 let Some(entry) = entry else {
                     return Task::none();
                 };
+                // a desktop file from outside the application directories would never show up
+                if !self.all_entries.iter().any(|e| e.id == entry.id) {
+                    error!("refusing to add {}, which isn't an installed app", entry.id);
+                    return Task::none();
+                }
+                // dropping an app on its own group leaves it where it is,
+                // instead of removing it once the drag finishes
+                if i == self.cur_group && self.dnd_icon.is_some() {
+                    self.reordered = true;
+                    return Task::none();
+                }
                 self.config.add_entry(i, &entry.id);
                 if let Some(helper) = self.helper.as_ref() {
                     if let Err(err) = self.config.write_entry(helper) {
@@ -709,19 +2538,73 @@ impl cosmic::Application for CosmicAppLibrary {
                     }
                 }
             }
+            Message::DropDesktopFile(path) => {
+                // dropping an app back into its own group leaves it where it is
+                if self.dnd_icon.is_some() {
+                    self.reordered = true;
+                    return Task::none();
+                }
+                if self.cur_group == 0 || self.config.is_history(self.cur_group) {
+                    return Task::none();
+                }
+                let Some(entry) = path
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "desktop"))
+                    .and_then(|path| load_desktop_file(None, path))
+                else {
+                    return Task::none();
+                };
+                return Task::batch(vec![
+                    self.update(Message::FinishDndOffer(self.cur_group, Some(entry))),
+                    self.filter_apps(),
+                ]);
+            }
             Message::LeaveDndOffer(i) => {
                 self.offer_group = self.offer_group.filter(|g| *g != i);
             }
-            Message::ScrollYOffset(y) => {
+            Message::ScrollYOffset(y, relative_y) => {
                 self.scroll_offset = y;
+                self.scroll_relative = Some(relative_y);
+                self.overscroll = 0.0;
             }
-            Message::ConfirmDelete => {
-                let mut cmds = vec![destroy_layer_surface(DELETE_GROUP_WINDOW_ID.clone())];
-                if let Some(group) = self.group_to_delete.take() {
-                    self.config.remove(group);
-                    if let Some(helper) = self.helper.as_ref() {
-                        if let Err(err) = self.config.write_entry(helper) {
-                            error!("{:?}", err);
+            Message::Wheel(delta) => {
+                if !self.config.scroll_switches_groups || self.menu.is_some() {
+                    return Task::none();
+                }
+                let (x, y) = match delta {
+                    mouse::ScrollDelta::Lines { x, y } => (x, y),
+                    mouse::ScrollDelta::Pixels { x, y } => {
+                        (x / PIXELS_PER_LINE, y / PIXELS_PER_LINE)
+                    }
+                };
+                // a missing or NaN offset means the apps fit without scrolling
+                let at_top = !self.scroll_relative.is_some_and(|r| r > 0.0);
+                let at_bottom = !self.scroll_relative.is_some_and(|r| r < 1.0);
+                if x != 0.0 {
+                    self.overscroll -= x;
+                } else if (y > 0.0 && at_top) || (y < 0.0 && at_bottom) {
+                    self.overscroll -= y;
+                } else {
+                    self.overscroll = 0.0;
+                }
+                if self.overscroll.abs() >= GROUP_SWITCH_OVERSCROLL {
+                    let next = if self.overscroll > 0.0 {
+                        self.cur_group.checked_add(1)
+                    } else {
+                        self.cur_group.checked_sub(1)
+                    };
+                    self.overscroll = 0.0;
+                    if let Some(next) = next {
+                        return self.update(Message::SelectGroup(next));
+                    }
+                }
+            }
+            Message::ConfirmDelete => {
+                let mut cmds = vec![destroy_popup(DELETE_GROUP_POPUP_ID.clone())];
+                if let Some(group) = self.group_to_delete.take() {
+                    self.config.remove(group);
+                    if let Some(helper) = self.helper.as_ref() {
+                        if let Err(err) = self.config.write_entry(helper) {
+                            error!("{:?}", err);
                         }
                     }
                     self.cur_group = 0;
@@ -731,14 +2614,50 @@ impl cosmic::Application for CosmicAppLibrary {
             }
             Message::CancelDelete => {
                 self.group_to_delete = None;
-                return destroy_layer_surface(DELETE_GROUP_WINDOW_ID.clone());
+                return destroy_popup(DELETE_GROUP_POPUP_ID.clone());
             }
-            Message::FilterApps(input, filtered_apps) => {
+            Message::FilterApps(input, filtered_apps, total) => {
                 self.entry_path_input = filtered_apps;
+                self.total_results = total;
                 self.waiting_for_filtered = false;
                 if self.search_value != input {
                     return self.filter_apps();
                 }
+                // the same app stays highlighted when the apps were reloaded
+                if let Some(id) = self.reload_highlight.take() {
+                    self.highlighted = self.entry_path_input.iter().position(|e| e.id == id);
+                } else {
+                    self.highlighted = (!self.search_value.is_empty()
+                        && !self.entry_path_input.is_empty())
+                    .then(|| {
+                        self.highlighted
+                            .unwrap_or_default()
+                            .min(self.entry_path_input.len() - 1)
+                    });
+                }
+                // fewer apps may not reach as far as they were scrolled
+                let max_offset =
+                    max_scroll_offset(self.entry_path_input.len(), self.app_row_height());
+                if self.scroll_offset > max_offset {
+                    self.scroll_offset = max_offset;
+                    let y = if max_offset > 0.0 { 1.0 } else { 0.0 };
+                    self.scroll_relative = Some(y);
+                    return scrollable::snap_to(
+                        self.app_grid_id(),
+                        scrollable::RelativeOffset { x: 0.0, y },
+                    );
+                }
+            }
+            Message::OpenAppStore => {
+                tokio::spawn(async move {
+                    cosmic::desktop::spawn_desktop_exec(
+                        APP_STORE_EXEC,
+                        Vec::<(String, String)>::new(),
+                        Some(APP_STORE_ID),
+                    )
+                    .await
+                });
+                return self.update(Message::Hide);
             }
             Message::GpuUpdate(gpus) => {
                 self.gpus = gpus;
@@ -751,6 +2670,22 @@ impl cosmic::Application for CosmicAppLibrary {
                     self.app_list_config.add_pinned(pinned_id, &app_list_helper);
                 }
             }
+            Message::PinToAppTrayStart(usize) => {
+                let pinned_id = self.entry_path_input.get(usize).map(|e| e.id.clone());
+                if let Some((pinned_id, app_list_helper)) = pinned_id
+                    .zip(Config::new(cosmic_app_list_config::APP_ID, AppListConfig::VERSION).ok())
+                {
+                    let mut favorites = self.app_list_config.favorites.clone();
+                    favorites.retain(|p| p != &pinned_id);
+                    favorites.insert(0, pinned_id);
+                    if let Err(err) = self
+                        .app_list_config
+                        .set_favorites(&app_list_helper, favorites)
+                    {
+                        error!("{:?}", err);
+                    }
+                }
+            }
             Message::UnPinFromAppTray(usize) => {
                 let pinned_id = self.entry_path_input.get(usize).map(|e| e.id.clone());
                 if let Some((pinned_id, app_list_helper)) = pinned_id
@@ -760,18 +2695,138 @@ impl cosmic::Application for CosmicAppLibrary {
                         .remove_pinned(&pinned_id, &app_list_helper);
                 }
             }
+            Message::ToggleGroupMembership(i, group) => {
+                let Some(entry) = self.entry_path_input.get(i).cloned() else {
+                    return Task::none();
+                };
+                if self
+                    .config
+                    .groups()
+                    .get(group)
+                    .is_some_and(|g| g.matches(&entry))
+                {
+                    self.config.remove_entry(group, &entry.id);
+                } else {
+                    self.config.add_entry(group, &entry.id);
+                }
+                if let Some(helper) = self.helper.as_ref() {
+                    if let Err(err) = self.config.write_entry(helper) {
+                        error!("{:?}", err);
+                    }
+                }
+                // the app may leave the shown apps, so the menu would point at another one
+                if group == self.cur_group {
+                    self.menu = None;
+                    return Task::batch(vec![destroy_popup(MENU_ID.clone()), self.filter_apps()]);
+                }
+                return self.filter_apps();
+            }
+            Message::ToggleFavorite(i, favorite) => {
+                let Some(id) = self.entry_path_input.get(i).map(|e| e.id.clone()) else {
+                    return Task::none();
+                };
+                self.keep_cur_group(|config| config.toggle_favorite(&id, favorite));
+                if let Some(helper) = self.helper.as_ref() {
+                    if let Err(err) = self.config.write_entry(helper) {
+                        error!("{:?}", err);
+                    }
+                }
+                return Task::batch(vec![
+                    self.update(Message::CloseContextMenu),
+                    self.filter_apps(),
+                ]);
+            }
+            Message::ClearHistory => {
+                self.state.history.clear();
+                if let Some(helper) = self.state_helper.as_ref() {
+                    if let Err(err) = self.state.write_entry(helper) {
+                        error!("{:?}", err);
+                    }
+                }
+                return self.filter_apps();
+            }
+            Message::ToggleSelecting => {
+                self.selecting = !self.selecting;
+                self.selection.clear();
+            }
+            Message::AddSelectionToGroup(group) => {
+                for id in std::mem::take(&mut self.selection) {
+                    self.config.add_entry(group, &id);
+                }
+                self.selecting = false;
+                if let Some(helper) = self.helper.as_ref() {
+                    if let Err(err) = self.config.write_entry(helper) {
+                        error!("{:?}", err);
+                    }
+                }
+                return self.filter_apps();
+            }
+            Message::RemoveSelection => {
+                let cur_group = self.cur_group;
+                let ids = std::mem::take(&mut self.selection);
+                self.keep_cur_group(|config| {
+                    for id in &ids {
+                        config.remove_entry(cur_group, id);
+                    }
+                });
+                self.selecting = false;
+                if let Some(helper) = self.helper.as_ref() {
+                    if let Err(err) = self.config.write_entry(helper) {
+                        error!("{:?}", err);
+                    }
+                }
+                return self.filter_apps();
+            }
+            Message::PinSelection => {
+                let ids = std::mem::take(&mut self.selection)
+                    .into_iter()
+                    .collect_vec();
+                self.set_pinned(&ids, true);
+                self.selecting = false;
+            }
             Message::AppListConfig(config) => {
                 self.app_list_config = config;
             }
+            Message::LibraryConfig(config) => {
+                if self.editing_groups() {
+                    self.pending_config = Some(config);
+                    return Task::none();
+                }
+                self.pending_config = None;
+                self.set_config(config);
+                return self.filter_apps();
+            }
+            Message::Status(status::Event::Ready(tx)) => {
+                self.status_tx = Some(tx);
+                self.publish_status();
+            }
         }
         Task::none()
     }
 
     fn dbus_activation(&mut self, msg: DbusActivationMessage) -> Task<Self::Message> {
-        if matches!(msg.msg, DbusActivationDetails::Activate) {
-            self.activate()
-        } else {
-            Task::none()
+        match msg.msg {
+            DbusActivationDetails::Activate => self.activate(),
+            DbusActivationDetails::ActivateAction { action, .. } => {
+                match ron::from_str::<LauncherCommands>(&action) {
+                    Ok(LauncherCommands::Refresh) => self.reload_apps(),
+                    // only when confirmed, since it can't be undone from the library
+                    Ok(LauncherCommands::Reset { yes: true }) => self.reset(),
+                    Ok(LauncherCommands::Search) => {
+                        if self.active_surface {
+                            return self.fade_out();
+                        }
+                        self.search_only = true;
+                        self.activate()
+                    }
+                    Ok(_) => Task::none(),
+                    Err(err) => {
+                        error!("{:?}", err);
+                        Task::none()
+                    }
+                }
+            }
+            _ => Task::none(),
         }
     }
 
@@ -782,6 +2837,7 @@ impl cosmic::Application for CosmicAppLibrary {
     fn view_window(&self, id: SurfaceId) -> Element<Message> {
         let Spacing {
             space_none,
+            space_xxxs,
             space_xxs,
             space_xs,
             space_s,
@@ -803,42 +2859,98 @@ impl cosmic::Application for CosmicAppLibrary {
                     .into();
             };
 
-            let mut list_column = Vec::new();
+            // which app this is, to tell duplicates apart
+            let source = menu
+                .path
+                .as_ref()
+                .map(|path| AppSource::from(path.as_path()).to_string());
+            let info = iter::once(menu.id.clone())
+                .chain(source)
+                .chain(self.menu_info.version.clone())
+                .join(" • ");
+            let launches = self
+                .state
+                .launches(&menu.id)
+                .map(|(count, days)| fl!("launch-count", count = count, days = days));
+            let mut list_column = vec![
+                container(column![caption(info)].push_maybe(launches.map(caption)))
+                    .padding(menu_control_padding())
+                    .into(),
+                divider::horizontal::light().into(),
+            ];
 
+            let default_gpu = self.default_gpu(menu);
             if let Some(gpus) = self.gpus.as_ref() {
                 for (j, gpu) in gpus.iter().enumerate() {
-                    let default_idx = if menu.prefers_dgpu {
-                        gpus.iter().position(|gpu| !gpu.default).unwrap_or(0)
-                    } else {
-                        gpus.iter().position(|gpu| gpu.default).unwrap_or(0)
-                    };
                     list_column.push(
-                        menu_button(body(format!(
-                            "{} {}",
-                            fl!("run-on", gpu = gpu.name.clone()),
-                            if j == default_idx {
-                                fl!("run-on-default")
-                            } else {
-                                String::new()
-                            }
-                        )))
-                        .on_press(Message::ActivateApp(*i, Some(j)))
+                        menu_button(body(if Some(j) == default_gpu {
+                            fl!("run-on-default-gpu", gpu = gpu.name.clone())
+                        } else {
+                            fl!("run-on", gpu = gpu.name.clone())
+                        }))
+                        .on_press(Message::ActivateApp(
+                            *i,
+                            LaunchOptions {
+                                gpu: Some(j),
+                                ..Default::default()
+                            },
+                        ))
                         .into(),
                     )
                 }
             } else {
                 list_column.push(
                     menu_button(body(RUN.clone()))
-                        .on_press(Message::ActivateApp(*i, None))
+                        .on_press(Message::ActivateApp(*i, LaunchOptions::default()))
                         .into(),
                 );
             }
+            list_column.push(
+                menu_button(body(if menu.terminal {
+                    fl!("run-detached")
+                } else {
+                    fl!("run-in-terminal")
+                }))
+                .on_press(Message::ActivateApp(
+                    *i,
+                    LaunchOptions {
+                        gpu: default_gpu,
+                        terminal: Some(!menu.terminal),
+                        ..Default::default()
+                    },
+                ))
+                .into(),
+            );
+
+            let alternates = menu
+                .path
+                .as_ref()
+                .and_then(|path| self.alternates.get(path));
+            if let Some(alternates) = alternates {
+                list_column.push(divider::horizontal::light().into());
+                for (j, alternate) in alternates.iter().enumerate() {
+                    let source = alternate
+                        .path
+                        .as_ref()
+                        .map(|path| AppSource::from(path.as_ref()).description())
+                        .unwrap_or_default();
+                    list_column.push(
+                        menu_button(body(fl!("run-variant", source = source)))
+                            .on_press(Message::ActivateAlternate(*i, j))
+                            .into(),
+                    );
+                }
+            }
 
-            if menu.desktop_actions.len() > 0 {
+            if !self.menu_info.actions.is_empty() {
                 list_column.push(divider::horizontal::light().into());
-                for action in menu.desktop_actions.iter() {
+                for (action, action_icon) in &self.menu_info.actions {
+                    let action_icon: Element<_> = match action_icon {
+                        Some(name) => icon::icon(from_name(name.as_str()).size(16).into()).into(),
+                        None => horizontal_space().width(16.0).into(),
+                    };
                     list_column.push(
-                        menu_button(body(&action.name))
+                        menu_button(row![action_icon, body(&action.name)].spacing(space_xxs))
                             .on_press(Message::SelectAction(
                                 MenuAction::DesktopAction(action.exec.clone()).into(),
                             ))
@@ -870,10 +2982,90 @@ impl cosmic::Application for CosmicAppLibrary {
             } else {
                 Message::PinToAppTray(*i)
             });
+            // starring is independent of the app tray
+            let is_favorite = self.config.favorites.contains(&menu.id);
+            let favorite = menu_button(
+                row![
+                    icon::icon(
+                        icon::from_name(if is_favorite {
+                            "starred-symbolic"
+                        } else {
+                            "non-starred-symbolic"
+                        })
+                        .size(16)
+                        .into()
+                    ),
+                    body(if is_favorite {
+                        fl!("remove-from-favorites")
+                    } else {
+                        fl!("add-to-favorites")
+                    })
+                ]
+                .spacing(space_xxs),
+            )
+            .on_press(Message::ToggleFavorite(*i, !is_favorite));
             list_column.push(divider::horizontal::light().into());
+            list_column.push(favorite.into());
             list_column.push(pin_to_app_tray.into());
+            if !is_pinned {
+                list_column.push(
+                    menu_button(
+                        row![
+                            horizontal_space().width(16.0),
+                            body(fl!("pin-to-app-tray-start"))
+                        ]
+                        .spacing(space_xxs),
+                    )
+                    .on_press(Message::PinToAppTrayStart(*i))
+                    .into(),
+                );
+            }
 
-            if self.cur_group > 0 {
+            // group membership
+            let groups = self.config.groups();
+            if groups.len() > 1 {
+                list_column.push(divider::horizontal::light().into());
+                list_column.push(
+                    container(caption(fl!("add-to-folder")))
+                        .padding(menu_control_padding())
+                        .into(),
+                );
+                for (j, group) in groups.iter().enumerate().skip(1) {
+                    if self.config.is_virtual(j) {
+                        continue;
+                    }
+                    let check: Element<_> = if group.matches(menu) {
+                        icon::icon(icon::from_name("checkbox-checked-symbolic").size(16).into())
+                            .class(cosmic::theme::Svg::Custom(svg_accent.clone()))
+                            .into()
+                    } else {
+                        horizontal_space().width(16.0).into()
+                    };
+                    list_column.push(
+                        menu_button(row![check, body(group.name())].spacing(space_xxs))
+                            .on_press(Message::ToggleGroupMembership(*i, j))
+                            .into(),
+                    );
+                }
+            }
+
+            list_column.push(divider::horizontal::light().into());
+            list_column.push(
+                menu_button(body(fl!("launch-options")))
+                    .on_press(Message::SelectAction(MenuAction::LaunchOptions))
+                    .into(),
+            );
+
+            if self.menu_info.editable {
+                list_column.push(divider::horizontal::light().into());
+                list_column.push(
+                    menu_button(body(fl!("edit-desktop-file")))
+                        .on_press(Message::SelectAction(MenuAction::EditDesktopFile))
+                        .into(),
+                );
+            }
+
+            if self.cur_group > 0 && !self.config.is_virtual(self.cur_group) {
                 list_column.push(divider::horizontal::light().into());
                 list_column.push(
                     menu_button(body(REMOVE.clone()))
@@ -885,19 +3077,7 @@ impl cosmic::Application for CosmicAppLibrary {
             return autosize(
                 container(scrollable(Column::with_children(list_column)))
                     .padding([8, 0])
-                    .class(theme::Container::Custom(Box::new(|theme| {
-                        container::Style {
-                            text_color: Some(theme.cosmic().on_bg_color().into()),
-                            background: Some(Color::from(theme.cosmic().background.base).into()),
-                            border: Border {
-                                color: theme.cosmic().bg_divider().into(),
-                                radius: theme.cosmic().corner_radii.radius_m.into(),
-                                width: 1.0,
-                            },
-                            shadow: Shadow::default(),
-                            icon_color: Some(theme.cosmic().on_bg_color().into()),
-                        }
-                    })))
+                    .class(theme::Container::Custom(Box::new(menu_style)))
                     .width(Length::Shrink)
                     .height(Length::Shrink)
                     .align_x(Horizontal::Center)
@@ -908,7 +3088,74 @@ impl cosmic::Application for CosmicAppLibrary {
             .max_width(300.)
             .into();
         }
-        if id == NEW_GROUP_WINDOW_ID.clone() {
+        if id == GROUP_MENU_ID.clone() {
+            let Some(group) = self.group_menu else {
+                return container(horizontal_space())
+                    .width(Length::Fixed(1.0))
+                    .height(Length::Fixed(1.0))
+                    .into();
+            };
+
+            let mut list_column = Vec::new();
+            if self.can_pin_group(group) {
+                list_column.push(
+                    menu_button(body(fl!("pin-all-to-app-tray")))
+                        .on_press(Message::PinGroupToAppTray(group, true))
+                        .into(),
+                );
+                list_column.push(
+                    menu_button(body(fl!("unpin-all-from-app-tray")))
+                        .on_press(Message::PinGroupToAppTray(group, false))
+                        .into(),
+                );
+            }
+            if group > 0 {
+                list_column.push(match self.open_all_confirm {
+                    Some(count) => menu_button(body(fl!("open-all-confirm", count = count)))
+                        .on_press(Message::OpenAll(group, true))
+                        .into(),
+                    None => menu_button(body(fl!("open-all")))
+                        .on_press(Message::OpenAll(group, false))
+                        .into(),
+                });
+            }
+            // library home, favorites and history can't be managed
+            if group > 0 && !self.config.is_virtual(group) {
+                list_column.push(divider::horizontal::light().into());
+                list_column.push(
+                    menu_button(body(fl!("rename")))
+                        .on_press(Message::RenameGroup(group))
+                        .into(),
+                );
+                list_column.push(
+                    menu_button(body(fl!("edit-icon")))
+                        .on_press(Message::EditGroupIcon(group))
+                        .into(),
+                );
+                list_column.push(
+                    menu_button(body(fl!("edit-filter")))
+                        .on_press(Message::StartEditGroupFilter(group))
+                        .into(),
+                );
+                list_column.push(
+                    menu_button(body(fl!("delete")))
+                        .on_press(Message::Delete(group, self.group_menu_bounds))
+                        .into(),
+                );
+            }
+
+            return autosize(
+                container(Column::with_children(list_column))
+                    .padding([8, 0])
+                    .class(theme::Container::Custom(Box::new(menu_style)))
+                    .width(Length::Shrink)
+                    .height(Length::Shrink),
+                GROUP_MENU_AUTOSIZE_ID.clone(),
+            )
+            .max_width(300.)
+            .into();
+        }
+        if id == NEW_GROUP_POPUP_ID.clone() {
             let Some(group_name) = self.new_group.as_ref() else {
                 return container(horizontal_space())
                     .width(Length::Fixed(1.0))
@@ -977,7 +3224,175 @@ impl cosmic::Application for CosmicAppLibrary {
             )
             .into();
         }
-        if id == DELETE_GROUP_WINDOW_ID.clone() {
+        if id == HELP_WINDOW_ID.clone() {
+            // bindings of the same action which follow each other share a row
+            let mut rows: Vec<(String, String, Option<String>)> = Vec::new();
+            for (key_bind, action) in &self.key_binds {
+                let description = action.description();
+                match rows.last_mut() {
+                    Some((last, _, to)) if *last == description => {
+                        *to = Some(key_bind.to_string());
+                    }
+                    _ => rows.push((description, key_bind.to_string(), None)),
+                }
+            }
+            let rows = rows.into_iter().map(|(description, from, to)| {
+                let keys = match to {
+                    Some(to) => format!("{from} – {to}"),
+                    None => from,
+                };
+                row![
+                    text(description).size(14.0).width(Length::Fill),
+                    text(keys).size(14.0)
+                ]
+                .spacing(space_s)
+                .into()
+            });
+            let dialog = column![
+                container(text(fl!("keyboard-shortcuts")).size(24))
+                    .align_x(Horizontal::Left)
+                    .width(Length::Fixed(432.0)),
+                container(Column::with_children(rows).spacing(space_xxs))
+                    .width(Length::Fixed(432.0)),
+                container(
+                    button::custom(
+                        text(fl!("close"))
+                            .size(14.0)
+                            .align_x(Horizontal::Center)
+                            .width(Length::Fill)
+                    )
+                    .on_press(Message::ToggleHelp)
+                    .padding([space_xxs, space_s])
+                    .width(142)
+                )
+                .width(Length::Fixed(432.0))
+                .align_x(Horizontal::Right)
+            ]
+            .align_x(Alignment::Center)
+            .spacing(space_s);
+            return autosize(
+                container(dialog)
+                    .class(theme::Container::Custom(Box::new(menu_style)))
+                    .width(Length::Shrink)
+                    .height(Length::Shrink)
+                    .padding(space_s),
+                HELP_AUTOSIZE_ID.clone(),
+            )
+            .into();
+        }
+        if id == LAUNCH_OPTIONS_WINDOW_ID.clone() {
+            let Some(editor) = self.launch_options_editor.as_ref() else {
+                return container(horizontal_space())
+                    .width(Length::Fixed(1.0))
+                    .height(Length::Fixed(1.0))
+                    .into();
+            };
+            let env_rows = editor
+                .env
+                .iter()
+                .enumerate()
+                .map(|(i, (key, value))| {
+                    row![
+                        text(format!("{key}={value}")).width(Length::Fill),
+                        button::custom(
+                            icon::icon(icon::from_name("edit-delete-symbolic").size(16).into())
+                                .width(Length::Fixed(16.0))
+                                .height(Length::Fixed(16.0)),
+                        )
+                        .padding(space_xxs)
+                        .class(Button::Icon)
+                        .on_press(Message::RemoveLaunchEnv(i)),
+                    ]
+                    .spacing(space_s)
+                    .align_y(Alignment::Center)
+                    .into()
+                })
+                .collect_vec();
+            let dialog = column![
+                container(
+                    text(fl!("launch-options-title", app = editor.app_name.clone())).size(24)
+                )
+                .align_x(Horizontal::Left)
+                .width(Length::Fixed(432.0)),
+                container(Column::with_children(env_rows).spacing(space_xxs))
+                    .width(Length::Fixed(432.0)),
+                text_input("NAME=value", &editor.input)
+                    .label(&ENVIRONMENT_VARIABLE)
+                    .on_input(Message::LaunchEnvInput)
+                    .on_submit(Message::AddLaunchEnv)
+                    .width(Length::Fixed(432.0))
+                    .size(14)
+                    .id(LAUNCH_ENV_ID.clone()),
+                container(
+                    row![
+                        button::custom(
+                            text(CANCEL.to_string())
+                                .size(14.0)
+                                .align_x(Horizontal::Center)
+                                .width(Length::Fill)
+                        )
+                        .on_press(Message::CancelLaunchOptions)
+                        .padding([space_xxs, space_s])
+                        .width(142),
+                        button::custom(
+                            text(SAVE.to_string())
+                                .size(14.0)
+                                .align_x(Horizontal::Center)
+                                .width(Length::Fill)
+                        )
+                        .class(Button::Suggested)
+                        // what's typed would be lost if it isn't a variable
+                        .on_press_maybe(
+                            (editor.input.trim().is_empty() || editor.parsed_input().is_some())
+                                .then_some(Message::SubmitLaunchOptions)
+                        )
+                        .padding([space_xxs, space_s])
+                        .width(142),
+                    ]
+                    .spacing(space_s)
+                )
+                .width(Length::Fixed(432.0))
+                .align_x(Horizontal::Right)
+            ]
+            .align_x(Alignment::Center)
+            .spacing(space_s);
+            return autosize(
+                container(dialog)
+                    .class(theme::Container::Custom(Box::new(|theme| {
+                        container::Style {
+                            text_color: Some(theme.cosmic().on_bg_color().into()),
+                            icon_color: Some(theme.cosmic().on_bg_color().into()),
+                            background: Some(Color::from(theme.cosmic().background.base).into()),
+                            border: Border {
+                                color: theme.cosmic().bg_divider().into(),
+                                radius: theme.cosmic().corner_radii.radius_m.into(),
+                                width: 1.0,
+                            },
+                            shadow: Shadow::default(),
+                        }
+                    })))
+                    .width(Length::Shrink)
+                    .height(Length::Shrink)
+                    .padding(space_s),
+                LAUNCH_OPTIONS_AUTOSIZE_ID.clone(),
+            )
+            .into();
+        }
+        if id == DELETE_GROUP_POPUP_ID.clone() {
+            let (app_count, is_dynamic) = self
+                .group_to_delete
+                .map(|group| {
+                    (
+                        self.config
+                            .filtered(group, "", &self.all_entries, &self.comments, &self.state)
+                            .len(),
+                        matches!(
+                            self.config.filter(group),
+                            Some(FilterType::Categories { .. })
+                        ),
+                    )
+                })
+                .unwrap_or_default();
             let dialog = column![
                 row![
                     container(
@@ -988,40 +3403,302 @@ impl cosmic::Application for CosmicAppLibrary {
                     .padding(8),
                     column![
                         text(fl!("delete-folder")).size(24),
-                        text(fl!("delete-folder", "msg"))
+                        text(fl!("delete-folder", "msg")),
+                        text(fl!("delete-folder-apps", count = app_count)),
+                    ]
+                    .push_maybe(is_dynamic.then(|| text(fl!("delete-folder-dynamic"))))
+                    .spacing(8)
+                    .width(Length::Fixed(360.0))
+                ]
+                .spacing(16),
+                container(
+                    row![
+                        button::custom(
+                            text(CANCEL.as_str())
+                                .align_x(Horizontal::Center)
+                                .width(Length::Fill)
+                        )
+                        .on_press(Message::CancelDelete)
+                        .padding([space_xxs, space_m])
+                        .width(142),
+                        button::custom(
+                            text(fl!("delete"))
+                                .align_x(Horizontal::Center)
+                                .width(Length::Fill)
+                        )
+                        .id(SUBMIT_DELETE_ID.clone())
+                        .class(Button::Destructive)
+                        .on_press(Message::ConfirmDelete)
+                        .padding([space_xxs, space_m])
+                        .width(142),
+                    ]
+                    .spacing(space_s)
+                )
+                .width(Length::Fixed(432.0))
+                .align_x(Horizontal::Right)
+            ]
+            .align_x(Alignment::Center)
+            .spacing(space_l);
+            return autosize(
+                container(dialog)
+                    .class(theme::Container::Custom(Box::new(|theme| {
+                        container::Style {
+                            text_color: Some(theme.cosmic().on_bg_color().into()),
+                            icon_color: Some(theme.cosmic().on_bg_color().into()),
+                            background: Some(Color::from(theme.cosmic().background.base).into()),
+                            border: Border {
+                                color: theme.cosmic().bg_divider().into(),
+                                radius: theme.cosmic().corner_radii.radius_m.into(),
+                                width: 1.0,
+                            },
+                            shadow: Shadow::default(),
+                        }
+                    })))
+                    .width(Length::Shrink)
+                    .height(Length::Shrink)
+                    .padding(space_m),
+                DELETE_GROUP_AUTOSIZE_ID.clone(),
+            )
+            .into();
+        }
+        if id == EDIT_GROUP_FILTER_WINDOW_ID.clone() {
+            let Some(GroupEditor {
+                filter:
+                    FilterType::Categories {
+                        categories,
+                        exclude,
+                        include,
+                        match_all,
+                        exclude_categories,
+                    },
+                accent,
+                icon: icon_name,
+                sort,
+                ..
+            }) = self.group_editor.as_ref()
+            else {
+                return container(horizontal_space())
+                    .width(Length::Fixed(1.0))
+                    .height(Length::Fixed(1.0))
+                    .into();
+            };
+
+            let all_categories = self
+                .all_entries
+                .iter()
+                .flat_map(|e| e.categories.iter())
+                .chain(categories.iter())
+                .chain(exclude_categories.iter())
+                .filter(|c| !c.is_empty())
+                .unique()
+                .sorted_by_key(|c| c.to_lowercase())
+                .map(|category| {
+                    let included = categories.contains(category);
+                    let excluded = exclude_categories.contains(category);
+                    row![
+                        text(category.as_str()).width(Length::Fill),
+                        checkbox(fl!("include"), included).on_toggle({
+                            let category = category.clone();
+                            move |enabled| Message::ToggleFilterCategory(category.clone(), enabled)
+                        }),
+                        checkbox(fl!("exclude"), excluded).on_toggle({
+                            let category = category.clone();
+                            move |enabled| {
+                                Message::ToggleFilterExcludedCategory(category.clone(), enabled)
+                            }
+                        }),
+                    ]
+                    .spacing(space_s)
+                    .align_y(Alignment::Center)
+                    .into()
+                })
+                .collect_vec();
+
+            let app_name = |id: &String| {
+                self.all_entries
+                    .iter()
+                    .find(|e| &e.id == id)
+                    .map(|e| e.name.clone())
+                    .unwrap_or_else(|| id.clone())
+            };
+            let overrides = include
+                .iter()
+                .map(|id| (id, fl!("always-included")))
+                .chain(exclude.iter().map(|id| (id, fl!("always-excluded"))))
+                .map(|(id, label)| {
+                    row![
+                        text(format!("{} ({label})", app_name(id))).width(Length::Fill),
+                        button::custom(
+                            icon::icon(icon::from_name("edit-delete-symbolic").size(16).into())
+                                .width(Length::Fixed(16.0))
+                                .height(Length::Fixed(16.0)),
+                        )
+                        .padding(space_xxs)
+                        .class(Button::Icon)
+                        .on_press(Message::RemoveFilterOverride(id.clone())),
                     ]
-                    .spacing(8)
-                    .width(Length::Fixed(360.0))
+                    .spacing(space_s)
+                    .align_y(Alignment::Center)
+                    .into()
+                })
+                .collect_vec();
+
+            let mut dialog = column![
+                container(text(fl!("edit-filter")).size(24))
+                    .align_x(Horizontal::Left)
+                    .width(Length::Fixed(432.0)),
+                text(fl!("categories")).size(14),
+                scrollable(Column::with_children(all_categories).spacing(space_xxs))
+                    .height(Length::Fixed(240.0))
+                    .width(Length::Fixed(432.0)),
+                container(
+                    checkbox(fl!("match-all-categories"), *match_all)
+                        .on_toggle(Message::SetFilterMatchAll)
+                )
+                .width(Length::Fixed(432.0)),
+            ]
+            .align_x(Alignment::Center)
+            .spacing(space_s);
+            // preview how many apps the filter matches, so a filter matching nothing stands out
+            let matching = self
+                .group_editor
+                .as_ref()
+                .map(|editor| {
+                    AppGroup {
+                        filter: editor.filter.clone(),
+                        ..Default::default()
+                    }
+                    .filtered("", &[], &self.all_entries, &self.comments)
+                    .len()
+                })
+                .unwrap_or_default();
+            dialog = dialog.push(
+                row![text(fl!("filter-matches", count = matching)).size(14)]
+                    .push_maybe((matching == 0).then(|| {
+                        icon::icon(icon::from_name("dialog-warning-symbolic").size(16).into())
+                            .width(Length::Fixed(16.0))
+                            .height(Length::Fixed(16.0))
+                    }))
+                    .spacing(space_xxs)
+                    .align_y(Alignment::Center)
+                    .width(Length::Fixed(432.0)),
+            );
+            let accents = iter::once(None)
+                .chain(THEME_ACCENTS.iter().map(|c| Some(c.to_string())))
+                .map(|color| {
+                    let selected = accent == &color;
+                    let swatch: Element<_> = match color.as_deref().and_then(parse_accent) {
+                        Some(c) => container(horizontal_space())
+                            .width(Length::Fixed(24.0))
+                            .height(Length::Fixed(24.0))
+                            .class(theme::Container::Custom(Box::new(move |theme| {
+                                container::Style {
+                                    background: Some(c.into()),
+                                    border: Border {
+                                        color: theme.cosmic().on_bg_color().into(),
+                                        radius: 12.0.into(),
+                                        width: if selected { 2.0 } else { 0.0 },
+                                    },
+                                    ..Default::default()
+                                }
+                            })))
+                            .into(),
+                        None => icon::icon(icon::from_name("edit-clear-symbolic").size(16).into())
+                            .width(Length::Fixed(24.0))
+                            .height(Length::Fixed(24.0))
+                            .into(),
+                    };
+                    button::custom(swatch)
+                        .padding(space_xxxs)
+                        .class(Button::Icon)
+                        .selected(selected)
+                        .on_press(Message::SetEditorAccent(color))
+                        .into()
+                })
+                .collect_vec();
+            dialog = dialog.push(
+                column![
+                    text(fl!("icon")).size(14),
+                    row![
+                        icon::icon(from_name(icon_name.clone()).size(32).into())
+                            .width(Length::Fixed(32.0))
+                            .height(Length::Fixed(32.0)),
+                        text_input(fl!("icon-placeholder"), icon_name)
+                            .on_input(Message::SetEditorIcon)
+                            .on_paste(Message::SetEditorIcon)
+                            .id(EDIT_GROUP_ICON_ID.clone())
+                            .width(Length::Fill)
+                            .size(14),
+                    ]
+                    .spacing(space_s)
+                    .align_y(Alignment::Center)
                 ]
-                .spacing(16),
+                .spacing(space_xxs)
+                .width(Length::Fixed(432.0)),
+            );
+            dialog = dialog.push(
+                column![
+                    text(fl!("accent-color")).size(14),
+                    row(accents).spacing(space_xxs)
+                ]
+                .spacing(space_xxs)
+                .width(Length::Fixed(432.0)),
+            );
+            let sort_orders = [
+                (SortOrder::Alphabetical, fl!("sort-alphabetical")),
+                (SortOrder::Frecency, fl!("sort-frecency")),
+                (SortOrder::Manual, fl!("sort-manual")),
+            ]
+            .into_iter()
+            .map(|(order, label)| {
+                radio(label, order, Some(*sort), Message::SetEditorSort)
+                    .size(16)
+                    .into()
+            })
+            .collect_vec();
+            dialog = dialog.push(
+                column![
+                    text(fl!("sort-order")).size(14),
+                    row(sort_orders).spacing(space_s)
+                ]
+                .spacing(space_xxs)
+                .width(Length::Fixed(432.0)),
+            );
+            if !overrides.is_empty() {
+                dialog = dialog.push(
+                    scrollable(Column::with_children(overrides).spacing(space_xxs))
+                        .height(Length::Shrink)
+                        .width(Length::Fixed(432.0)),
+                );
+            }
+            let dialog = dialog.push(
                 container(
                     row![
                         button::custom(
-                            text(CANCEL.as_str())
+                            text(CANCEL.to_string())
+                                .size(14.0)
                                 .align_x(Horizontal::Center)
                                 .width(Length::Fill)
                         )
-                        .on_press(Message::CancelDelete)
-                        .padding([space_xxs, space_m])
+                        .on_press(Message::CancelEditGroupFilter)
+                        .padding([space_xxs, space_s])
                         .width(142),
                         button::custom(
-                            text(fl!("delete"))
+                            text(SAVE.to_string())
+                                .size(14.0)
                                 .align_x(Horizontal::Center)
                                 .width(Length::Fill)
                         )
-                        .id(SUBMIT_DELETE_ID.clone())
-                        .class(Button::Destructive)
-                        .on_press(Message::ConfirmDelete)
-                        .padding([space_xxs, space_m])
+                        .class(Button::Suggested)
+                        .on_press(Message::SubmitGroupEditor)
+                        .padding([space_xxs, space_s])
                         .width(142),
                     ]
-                    .spacing(space_s)
+                    .spacing(space_s),
                 )
                 .width(Length::Fixed(432.0))
-                .align_x(Horizontal::Right)
-            ]
-            .align_x(Alignment::Center)
-            .spacing(space_l);
+                .align_x(Horizontal::Right),
+            );
             return autosize(
                 container(dialog)
                     .class(theme::Container::Custom(Box::new(|theme| {
@@ -1039,22 +3716,57 @@ impl cosmic::Application for CosmicAppLibrary {
                     })))
                     .width(Length::Shrink)
                     .height(Length::Shrink)
-                    .padding(space_m),
-                DELETE_GROUP_AUTOSIZE_ID.clone(),
+                    .padding(space_s),
+                EDIT_GROUP_FILTER_AUTOSIZE_ID.clone(),
             )
             .into();
         }
 
         let cur_group = self.config.groups()[self.cur_group];
         let top_row = if self.cur_group == 0 {
+            let recent_searches = (self.search_value.is_empty()
+                && !self.state.recent_searches.is_empty())
+            .then(|| {
+                row(self
+                    .state
+                    .recent_searches
+                    .iter()
+                    .map(|search| {
+                        button::custom(text(search).size(12))
+                            .class(Button::Standard)
+                            .padding([space_none, space_xs])
+                            .on_press(Message::InputChanged(search.clone()))
+                            .into()
+                    })
+                    .collect_vec())
+                .spacing(space_xxs)
+            });
             row![container(
-                search_input(SEARCH_PLACEHOLDER.as_str(), self.search_value.as_str())
-                    .on_input(Message::InputChanged)
-                    .on_paste(Message::InputChanged)
-                    .style(TextInput::Search)
-                    .width(Length::Fixed(400.0))
-                    .size(14)
-                    .id(SEARCH_ID.clone())
+                column![tooltip(
+                    {
+                        let input =
+                            search_input(SEARCH_PLACEHOLDER.as_str(), self.search_value.as_str())
+                                .on_input(Message::InputChanged)
+                                .on_paste(Message::InputChanged)
+                                .on_submit(Message::ActivateHighlighted)
+                                .style(TextInput::Search)
+                                .width(Length::Fixed(400.0))
+                                .size(14)
+                                .id(SEARCH_ID.clone());
+                        let input = if self.search_value.is_empty() {
+                            input
+                        } else {
+                            input.on_clear(Message::ClearSearch)
+                        };
+                        // middle-click pastes the primary selection, only over the search input
+                        mouse_area(input).on_middle_press(Message::PastePrimary)
+                    },
+                    text(fl!("search-syntax")),
+                    tooltip::Position::Bottom,
+                )]
+                .push_maybe(recent_searches)
+                .spacing(space_xxs)
+                .align_x(Alignment::Center)
             )
             .align_y(Vertical::Center)
             .height(Length::Fixed(96.0))]
@@ -1075,97 +3787,282 @@ impl cosmic::Application for CosmicAppLibrary {
                             .size(14),
                     )
                 } else {
-                    container(text(cur_group.name()).size(24))
+                    let name = cur_group.name();
+                    match truncated_name(&name, MAX_GROUP_HEADER_NAME_LEN) {
+                        Some(short) => container(tooltip(
+                            text(short).size(24),
+                            text(name),
+                            tooltip::Position::Bottom,
+                        )),
+                        None => container(text(name).size(24)),
+                    }
                 },
-                row![
-                    horizontal_space(),
-                    tooltip(
-                        {
-                            let mut b = button::custom(
-                                icon::icon(icon::from_name("edit-symbolic").into())
-                                    .width(Length::Fixed(32.0))
-                                    .height(Length::Fixed(32.0)),
-                            )
-                            .padding(space_xs)
-                            .class(Button::Icon);
-                            if self.edit_name.is_none() {
-                                b = b.on_press(Message::StartEditName(cur_group.name()));
-                            }
-                            container(b)
-                                .height(Length::Fixed(96.0))
-                                .align_y(Vertical::Center)
-                        },
-                        text(fl!("rename")),
-                        tooltip::Position::Bottom
-                    ),
-                    tooltip(
+                if self.config.is_history(self.cur_group) {
+                    row![horizontal_space()].push(tooltip(
                         container(
                             button::custom(
-                                icon::icon(icon::from_name("edit-delete-symbolic").into())
+                                icon::icon(icon::from_name("edit-clear-history-symbolic").into())
                                     .width(Length::Fixed(32.0))
                                     .height(Length::Fixed(32.0)),
                             )
                             .padding(space_xs)
                             .class(Button::Icon)
-                            .on_press(Message::Delete(self.cur_group))
+                            .on_press(Message::ClearHistory),
                         )
                         .height(Length::Fixed(96.0))
                         .align_y(Vertical::Center),
-                        text(fl!("delete")),
-                        tooltip::Position::Bottom
-                    )
-                ]
+                        text(fl!("clear-history")),
+                        tooltip::Position::Bottom,
+                    ))
+                } else if self.config.is_favorites(self.cur_group) {
+                    row![horizontal_space()]
+                } else {
+                    row![horizontal_space()]
+                        .push(tooltip(
+                            container(
+                                button::custom(
+                                    icon::icon(
+                                        icon::from_name("document-properties-symbolic").into(),
+                                    )
+                                    .width(Length::Fixed(32.0))
+                                    .height(Length::Fixed(32.0)),
+                                )
+                                .padding(space_xs)
+                                .class(Button::Icon)
+                                .on_press(Message::StartEditGroupFilter(self.cur_group)),
+                            )
+                            .height(Length::Fixed(96.0))
+                            .align_y(Vertical::Center),
+                            text(fl!("edit-filter")),
+                            tooltip::Position::Bottom,
+                        ))
+                        .push(tooltip(
+                            {
+                                let mut b = button::custom(
+                                    icon::icon(icon::from_name("edit-symbolic").into())
+                                        .width(Length::Fixed(32.0))
+                                        .height(Length::Fixed(32.0)),
+                                )
+                                .padding(space_xs)
+                                .class(Button::Icon);
+                                if self.edit_name.is_none() {
+                                    b = b.on_press(Message::StartEditName(cur_group.name()));
+                                }
+                                container(b)
+                                    .height(Length::Fixed(96.0))
+                                    .align_y(Vertical::Center)
+                            },
+                            text(fl!("rename")),
+                            tooltip::Position::Bottom,
+                        ))
+                        .push(tooltip(
+                            container(
+                                button::custom(
+                                    icon::icon(icon::from_name("edit-delete-symbolic").into())
+                                        .width(Length::Fixed(32.0))
+                                        .height(Length::Fixed(32.0)),
+                                )
+                                .padding(space_xs)
+                                .class(Button::Icon)
+                                .on_press_with_rectangle(
+                                    |offset, bounds| {
+                                        Message::Delete(self.cur_group, bounds + offset)
+                                    },
+                                ),
+                            )
+                            .height(Length::Fixed(96.0))
+                            .align_y(Vertical::Center),
+                            text(fl!("delete")),
+                            tooltip::Position::Bottom,
+                        ))
+                }
                 .spacing(space_xxs)
+                .align_y(Alignment::Center)
                 .width(Length::FillPortion(1))
             ]
             .padding([0, space_l])
             .align_y(Alignment::Center)
         };
+        // without click-outside-to-close, the library needs another way to be closed by pointer
+        let close_button = (!self.config.click_outside_to_close).then(|| {
+            tooltip(
+                button::custom(
+                    icon::icon(icon::from_name("window-close-symbolic").into())
+                        .width(Length::Fixed(16.0))
+                        .height(Length::Fixed(16.0)),
+                )
+                .padding(space_xs)
+                .class(Button::Icon)
+                .on_press(Message::Hide),
+                text(fl!("close")),
+                tooltip::Position::Bottom,
+            )
+        });
+        let refresh_button = tooltip(
+            button::custom(
+                icon::icon(icon::from_name("view-refresh-symbolic").into())
+                    .width(Length::Fixed(16.0))
+                    .height(Length::Fixed(16.0)),
+            )
+            .padding(space_xs)
+            .class(Button::Icon)
+            .on_press(Message::Refresh),
+            text(fl!("refresh")),
+            tooltip::Position::Bottom,
+        );
+        let select_button = tooltip(
+            button::custom(
+                icon::icon(icon::from_name("selection-mode-symbolic").into())
+                    .width(Length::Fixed(16.0))
+                    .height(Length::Fixed(16.0)),
+            )
+            .padding(space_xs)
+            .class(if self.selecting {
+                Button::Suggested
+            } else {
+                Button::Icon
+            })
+            .on_press(Message::ToggleSelecting),
+            text(fl!("select-apps")),
+            tooltip::Position::Bottom,
+        );
+        let top_row = top_row
+            .push(select_button)
+            .push(refresh_button)
+            .push_maybe(close_button);
+        let header_accent = cur_group.accent_color();
+        let top_row = container(top_row)
+            .width(Length::Fill)
+            .align_x(Horizontal::Center)
+            .class(theme::Container::Custom(Box::new(move |theme| {
+                container::Style {
+                    background: header_accent.map(|c| Color { a: 0.2, ..c }.into()),
+                    border: Border {
+                        radius: theme.cosmic().corner_radii.radius_m.into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }
+            })));
 
+        let reorderable = self.cur_group > 0
+            && !self.config.disable_drag_and_drop
+            && self.search_value.is_empty()
+            && cur_group.sort == SortOrder::Manual;
+        let row_height = self.app_row_height();
+        let tile_height = row_height - space_xxs as f32;
+        let rows = self.entry_path_input.len().div_ceil(APPS_PER_ROW);
+        let grid_height = rows as f32 * row_height;
+        // when the apps scroll, only the rows in view are built
+        let visible_rows = if grid_height > APP_GRID_MAX_HEIGHT {
+            let first = (self.scroll_offset / row_height) as usize;
+            let last = ((self.scroll_offset + APP_GRID_MAX_HEIGHT) / row_height).ceil() as usize;
+            let start = first.saturating_sub(APP_GRID_ROW_BUFFER).min(rows);
+            start..(last + APP_GRID_ROW_BUFFER).clamp(start, rows)
+        } else {
+            0..rows
+        };
+        // the rows which aren't built are replaced by space, to keep the scrollbar in proportion
+        let spacer = |count: usize| -> Option<Element<_>> {
+            (count > 0).then(|| {
+                vertical_space()
+                    .height(Length::Fixed(count as f32 * row_height - space_xxs as f32))
+                    .into()
+            })
+        };
         // TODO grid widget in libcosmic
-        let app_grid_list: Vec<_> = self
+        let mut app_grid_list: Vec<_> = self
             .entry_path_input
             .iter()
             .enumerate()
+            .skip(visible_rows.start * APPS_PER_ROW)
+            .take(visible_rows.len() * APPS_PER_ROW)
             .map(|(i, entry)| {
-                let gpu_idx = self.gpus.as_ref().map(|gpus| {
-                    if entry.prefers_dgpu {
-                        gpus.iter().position(|gpu| !gpu.default).unwrap_or(0)
-                    } else {
-                        gpus.iter().position(|gpu| gpu.default).unwrap_or(0)
-                    }
-                });
+                let gpu_idx = self.launch_gpu(entry);
                 let dup = entry
                     .path
                     .as_ref()
                     .and_then(|path| self.duplicates.get(path));
                 let selected = self.menu.is_some_and(|m| m == i);
+                let is_pinned = self
+                    .app_list_config
+                    .favorites
+                    .iter()
+                    .any(|p| p == &entry.id);
 
                 let b = ApplicationButton::new(
                     &entry,
                     move |rect| Message::OpenContextMenu(rect, i),
                     if self.menu.is_none() {
-                        Some(Message::ActivateApp(i, gpu_idx))
+                        Some(Message::ActivateApp(
+                            i,
+                            LaunchOptions {
+                                gpu: gpu_idx,
+                                ..Default::default()
+                            },
+                        ))
                     } else if selected {
                         Some(Message::CloseContextMenu)
                     } else {
                         None
                     },
+                    self.menu
+                        .is_none()
+                        .then_some(Message::ActivateNewInstance(i)),
                     // TODO add icon and text if duplicated
                     dup,
-                    selected,
+                    selected || self.highlighted == Some(i) || self.selection.contains(&entry.id),
                     self.menu.is_none().then_some(Message::StartDrag(i)),
                     self.menu.is_none().then_some(Message::FinishDrag(false)),
                     self.menu.is_none().then_some(Message::CancelDrag),
+                    self.drag_threshold(),
+                    is_pinned,
+                    self.hidden_entries.contains(&entry.id),
+                    entry
+                        .path
+                        .as_ref()
+                        .is_some_and(|path| self.alternates.contains_key(path)),
+                    self.config.two_line_names,
+                    (i, self.entry_path_input.len()),
                 );
+                if !reorderable {
+                    return b.into();
+                }
 
-                b.into()
+                // drop on a tile to move the dragged app in front of it
+                let indicator = (self.reorder_target == Some(i)).then(|| {
+                    container(vertical_space())
+                        .width(Length::Fixed(4.0))
+                        .height(Length::Fixed(tile_height))
+                        .class(theme::Container::Custom(Box::new(|theme| {
+                            container::Style {
+                                background: Some(Color::from(theme.cosmic().accent_color()).into()),
+                                border: Border {
+                                    radius: 2.0.into(),
+                                    ..Default::default()
+                                },
+                                ..Default::default()
+                            }
+                        })))
+                });
+                dnd_destination_for_data::<AppletString, Message>(
+                    row![]
+                        .push_maybe(indicator)
+                        .push(b)
+                        .spacing(space_xxxs)
+                        .align_y(Alignment::Center)
+                        .width(Length::FillPortion(1)),
+                    move |data, _| Message::ReorderDrop(i, data.map(|data| data.0)),
+                )
+                .on_enter(move |_, _, _| Message::ReorderOffer(Some(i)))
+                .on_leave(|| Message::ReorderOffer(None))
+                .into()
             })
-            .chunks(7)
+            .chunks(APPS_PER_ROW)
             .into_iter()
             .map(|row_chunk| {
                 let mut new_row = row_chunk.collect_vec();
-                let missing = 7 - new_row.len();
+                let missing = APPS_PER_ROW - new_row.len();
                 if missing > 0 {
                     new_row.push(
                         iced::widget::horizontal_space()
@@ -1176,32 +4073,87 @@ impl cosmic::Application for CosmicAppLibrary {
                 row(new_row).spacing(space_xxs).into()
             })
             .collect();
+        if let Some(top) = spacer(visible_rows.start) {
+            app_grid_list.insert(0, top);
+        }
+        app_grid_list.extend(spacer(rows - visible_rows.end));
 
-        let app_scrollable = container(
-            scrollable(
-                column(app_grid_list)
-                    .width(Length::Fill)
-                    .spacing(space_xxs)
-                    .padding([space_none, space_xxl, space_xxs, space_xxl]),
+        let app_grid = if self.all_entries.is_empty() {
+            // nothing is installed, or the library can't see the apps
+            column![
+                icon::icon(icon::from_name("system-software-install-symbolic").into())
+                    .width(Length::Fixed(64.0))
+                    .height(Length::Fixed(64.0)),
+                text(fl!("no-apps-installed")).size(20),
+                text(fl!("no-apps-installed-hint")).size(14),
+                button::custom(text(fl!("open-app-store")).size(14.0))
+                    .class(Button::Suggested)
+                    .on_press(Message::OpenAppStore)
+                    .padding([space_xxs, space_s]),
+            ]
+            .spacing(space_xs)
+            .align_x(Alignment::Center)
+            .width(Length::Fill)
+        } else if app_grid_list.is_empty() {
+            let hint = if !self.search_value.is_empty() {
+                Some(fl!("no-apps-search-hint"))
+            } else if self.config.is_history(self.cur_group) {
+                Some(fl!("no-apps-history-hint"))
+            } else if self.config.is_favorites(self.cur_group) {
+                None
+            } else if self.cur_group > 0 {
+                Some(fl!("no-apps-group-hint"))
+            } else {
+                None
+            };
+            column![
+                icon::icon(icon::from_name("system-search-symbolic").into())
+                    .width(Length::Fixed(64.0))
+                    .height(Length::Fixed(64.0)),
+                text(fl!("no-apps")).size(20),
+            ]
+            .push_maybe(hint.map(|hint| text(hint).size(14)))
+            .spacing(space_xs)
+            .align_x(Alignment::Center)
+            .width(Length::Fill)
+        } else {
+            column(app_grid_list)
+                .width(Length::Fill)
+                .spacing(space_xxs)
+                .padding([space_none, space_xxl, space_xxs, space_xxl])
+        };
+        // center the apps when they fit without scrolling
+        let app_scrollable = if grid_height <= APP_GRID_MAX_HEIGHT {
+            container(app_grid).center_y(Length::Fill)
+        } else {
+            container(
+                scrollable(app_grid)
+                    .on_scroll(|viewport| {
+                        Message::ScrollYOffset(
+                            viewport.absolute_offset().y,
+                            viewport.relative_offset().y,
+                        )
+                    })
+                    .id(self.app_grid_id())
+                    .height(Length::Fill),
             )
-            .on_scroll(|viewport| Message::ScrollYOffset(viewport.absolute_offset().y))
-            .id(Id::new(
-                self.config
-                    .groups()
-                    .get(self.cur_group)
-                    .map(|g| g.name.clone())
-                    .unwrap_or_else(|| "unknown-group".to_string()),
-            ))
-            .height(Length::Fill),
-        )
-        .max_height(444.0);
-
-        // TODO use the spacing variables from the theme
-        let (group_icon_size, h_padding, group_width, chunks) = if self.config.groups().len() > 15 {
-            (16.0, space_xxs, 96.0, 11)
+        }
+        .max_height(APP_GRID_MAX_HEIGHT);
+        // desktop files dropped anywhere on the apps are added to the current group
+        let app_scrollable: Element<_> = if self.cur_group > 0
+            && !self.config.is_history(self.cur_group)
+            && !self.config.disable_drag_and_drop
+        {
+            dnd_destination_for_data::<AppletString, Message>(app_scrollable, |data, _| {
+                Message::DropDesktopFile(data.map(|data| data.0))
+            })
+            .into()
         } else {
-            (32.0, space_s, 128.0, 8)
+            app_scrollable.into()
         };
+
+        let (group_icon_size, h_padding, group_width) = self.group_tile_size();
+        let chunks = self.group_columns();
         let group_height =
             group_icon_size + 20.0 + (space_none as f32) + (space_xxs as f32) + (space_s as f32);
 
@@ -1225,7 +4177,7 @@ impl cosmic::Application for CosmicAppLibrary {
             .width(Length::Fixed(group_width))
             .class(theme::Button::IconVertical)
             .padding([space_none, h_padding, space_xxs, h_padding])
-            .on_press(Message::StartNewGroup),
+            .on_press_with_rectangle(|offset, bounds| Message::StartNewGroup(bounds + offset)),
         );
         let mut group_rows: Vec<_> = self
             .config
@@ -1239,54 +4191,145 @@ impl cosmic::Application for CosmicAppLibrary {
                     .align_y(Alignment::Center);
                 for (i, group) in groups.iter().enumerate() {
                     let i = i + chunk * chunks;
-                    let group_button = dnd_destination_for_data::<AppletString, Message>(
-                        button::custom(
-                            column![
-                                container(
-                                    icon::icon(from_name(group.icon.clone()).into())
-                                        .width(Length::Fixed(group_icon_size))
-                                        .height(Length::Fixed(group_icon_size))
-                                )
-                                .padding(space_xxs),
-                                text(group.name()).align_x(Horizontal::Center).size(14)
-                            ]
-                            .align_x(Alignment::Center)
-                            .width(Length::Fill),
-                        )
-                        .height(Length::Fixed(group_height))
-                        .width(Length::Fixed(group_width))
-                        .class(
-                            if self.offer_group == Some(i)
-                                || (self.cur_group == i && self.offer_group.is_none())
-                            {
-                                // TODO customize the IconVertical to highlight in the way we need
-                                Button::Custom {
-                                    active: Box::new(|focused, theme| {
-                                        let s =
-                                            theme.pressed(focused, false, &Button::IconVertical);
-                                        s
-                                    }),
-                                    disabled: Box::new(|theme| {
-                                        let s = theme.disabled(&Button::IconVertical);
-                                        s
-                                    }),
-                                    hovered: Box::new(|focused, theme| {
-                                        let s =
-                                            theme.hovered(focused, false, &Button::IconVertical);
-                                        s
-                                    }),
-                                    pressed: Box::new(|focused, theme| {
-                                        let s =
-                                            theme.pressed(focused, false, &Button::IconVertical);
-                                        s
-                                    }),
-                                }
-                            } else {
-                                Button::IconVertical
+                    let accent = group.accent_color();
+                    let name = group.name();
+                    let short_name = truncated_name(&name, MAX_GROUP_TILE_NAME_LEN);
+                    // dropping the app here wouldn't change anything
+                    let already_here = self.offer_group == Some(i) && self.offer_contains;
+                    let label = if already_here {
+                        fl!("already-in-folder")
+                    } else {
+                        short_name.clone().unwrap_or_else(|| name.clone())
+                    };
+                    let group_button = button::custom(
+                        column![
+                            container(
+                                icon::icon(from_name(group.icon.clone()).into())
+                                    .width(Length::Fixed(group_icon_size))
+                                    .height(Length::Fixed(group_icon_size))
+                                    .class(match accent {
+                                        Some(color) => {
+                                            cosmic::theme::Svg::Custom(Rc::new(move |_| {
+                                                svg::Style { color: Some(color) }
+                                            }))
+                                        }
+                                        None => cosmic::theme::Svg::Default,
+                                    })
+                            )
+                            .padding(space_xxs),
+                            text(label).align_x(Horizontal::Center).size(14)
+                        ]
+                        .align_x(Alignment::Center)
+                        .width(Length::Fill),
+                    )
+                    .height(Length::Fixed(group_height))
+                    .width(Length::Fixed(group_width))
+                    .class(
+                        if (self.offer_group == Some(i) && !already_here)
+                            || (self.cur_group == i && self.offer_group.is_none())
+                        {
+                            // TODO customize the IconVertical to highlight in the way we need
+                            Button::Custom {
+                                active: Box::new(move |focused, theme| {
+                                    let mut s =
+                                        theme.pressed(focused, false, &Button::IconVertical);
+                                    if let Some(accent) = accent {
+                                        s.background = Some(Color { a: 0.3, ..accent }.into());
+                                    }
+                                    s
+                                }),
+                                disabled: Box::new(|theme| {
+                                    let s = theme.disabled(&Button::IconVertical);
+                                    s
+                                }),
+                                hovered: Box::new(|focused, theme| {
+                                    let s = theme.hovered(focused, false, &Button::IconVertical);
+                                    s
+                                }),
+                                pressed: Box::new(move |focused, theme| {
+                                    let mut s =
+                                        theme.pressed(focused, false, &Button::IconVertical);
+                                    if let Some(accent) = accent {
+                                        s.background = Some(Color { a: 0.3, ..accent }.into());
+                                    }
+                                    s
+                                }),
+                            }
+                        } else if self.config.distinct_folders {
+                            Button::Custom {
+                                active: Box::new(|focused, theme| {
+                                    folder_style(
+                                        theme.active(focused, false, &Button::IconVertical),
+                                        theme,
+                                    )
+                                }),
+                                disabled: Box::new(|theme| {
+                                    folder_style(theme.disabled(&Button::IconVertical), theme)
+                                }),
+                                hovered: Box::new(|focused, theme| {
+                                    folder_style(
+                                        theme.hovered(focused, false, &Button::IconVertical),
+                                        theme,
+                                    )
+                                }),
+                                pressed: Box::new(|focused, theme| {
+                                    folder_style(
+                                        theme.pressed(focused, false, &Button::IconVertical),
+                                        theme,
+                                    )
+                                }),
+                            }
+                        } else {
+                            Button::IconVertical
+                        },
+                    )
+                    .padding([space_none, h_padding, space_xxs, h_padding])
+                    .on_press_maybe(self.menu.is_none().then_some(
+                        // selected apps are added to the folder clicked while selecting
+                        if self.selecting
+                            && !self.selection.is_empty()
+                            && i > 0
+                            && !self.config.is_history(i)
+                        {
+                            Message::AddSelectionToGroup(i)
+                        } else {
+                            Message::SelectGroup(i)
+                        },
+                    ));
+                    // library home has nothing to manage, and pinning all of its apps would pin every app
+                    let group_button: Element<_> = if i == 0 {
+                        group_button.into()
+                    } else {
+                        GroupButton::new(group_button, move |rect| Message::OpenGroupMenu(rect, i))
+                            .into()
+                    };
+                    let group_button: Element<_> = if short_name.is_some() {
+                        tooltip(group_button, text(name), tooltip::Position::Top).into()
+                    } else {
+                        group_button.into()
+                    };
+                    // a focus ring around the group focused with the keyboard
+                    let focused = self.highlighted_group == Some(i);
+                    let group_button = container(group_button).class(theme::Container::Custom(
+                        Box::new(move |theme| container::Style {
+                            border: Border {
+                                color: if focused {
+                                    theme.cosmic().accent_color().into()
+                                } else {
+                                    Color::TRANSPARENT
+                                },
+                                radius: theme.cosmic().corner_radii.radius_s.into(),
+                                width: 2.0,
                             },
-                        )
-                        .padding([space_none, h_padding, space_xxs, h_padding])
-                        .on_press_maybe(self.menu.is_none().then_some(Message::SelectGroup(i))),
+                            ..Default::default()
+                        }),
+                    ));
+                    if self.config.disable_drag_and_drop {
+                        group_row = group_row.push(group_button);
+                        continue;
+                    }
+                    let group_button = dnd_destination_for_data::<AppletString, Message>(
+                        group_button,
                         move |data, _| {
                             Message::FinishDndOffer(
                                 i,
@@ -1317,49 +4360,108 @@ impl cosmic::Application for CosmicAppLibrary {
         let group_rows =
             Column::with_children(group_rows.into_iter().map(|r| r.into()).collect_vec());
 
-        let content = column![
-            top_row,
-            app_scrollable,
-            container(horizontal_rule(1))
+        let truncated = (!self.search_value.is_empty()
+            && self.entry_path_input.len() < self.total_results)
+            .then(|| {
+                text(fl!(
+                    "showing-results",
+                    shown = self.entry_path_input.len(),
+                    total = self.total_results
+                ))
+                .size(12)
+            });
+        let selection_bar = self.selecting.then(|| {
+            let removable = self.cur_group > 0 && !self.config.is_history(self.cur_group);
+            let has_selection = !self.selection.is_empty();
+            let label = if has_selection {
+                fl!("selected-apps", count = self.selection.len())
+            } else {
+                fl!("select-apps-hint")
+            };
+            row![text(label).size(14).width(Length::Fill)]
+                .push_maybe(removable.then(|| {
+                    button::custom(text(fl!("remove-from-folder")).size(14.0))
+                        .class(Button::Standard)
+                        .padding([space_xxs, space_s])
+                        .on_press_maybe(has_selection.then_some(Message::RemoveSelection))
+                }))
+                .push(
+                    button::custom(text(fl!("pin-to-app-tray")).size(14.0))
+                        .class(Button::Standard)
+                        .padding([space_xxs, space_s])
+                        .on_press_maybe(has_selection.then_some(Message::PinSelection)),
+                )
+                .push(
+                    button::custom(text(fl!("done")).size(14.0))
+                        .class(Button::Suggested)
+                        .padding([space_xxs, space_s])
+                        .on_press(Message::ToggleSelecting),
+                )
+                .spacing(space_xs)
                 .padding([space_none, space_xxl])
-                .width(Length::Fill),
-            group_rows
-        ]
+                .align_y(Alignment::Center)
+        });
+        let folders_only =
+            self.config.folders_first && self.cur_group == 0 && self.search_value.is_empty();
+        let search_only = self.search_only && self.search_value.is_empty();
+        let content = if search_only {
+            column![top_row]
+        } else if folders_only {
+            // the groups take the place of the apps
+            column![top_row, container(group_rows).center_y(Length::Fill)]
+        } else {
+            column![top_row, app_scrollable]
+                .push_maybe(truncated)
+                .push_maybe(selection_bar)
+                .push(
+                    container(horizontal_rule(1))
+                        .padding([space_none, space_xxl])
+                        .width(Length::Fill),
+                )
+                .push(group_rows)
+        }
         .align_x(Alignment::Center);
 
+        let progress = self.anim_progress;
         let window = container(content)
-            .height(Length::Fill)
+            .height(if search_only {
+                Length::Shrink
+            } else {
+                Length::Fill
+            })
             .max_height(685)
-            .max_width(1200.0)
-            .class(theme::Container::Custom(Box::new(|theme| {
+            .max_width(MAX_WIDTH)
+            .class(theme::Container::Custom(Box::new(move |theme| {
+                let fade = |color: Color| Color {
+                    a: color.a * progress,
+                    ..color
+                };
                 container::Style {
-                    text_color: Some(theme.cosmic().on_bg_color().into()),
-                    background: Some(Color::from(theme.cosmic().background.base).into()),
+                    text_color: Some(fade(theme.cosmic().on_bg_color().into())),
+                    background: Some(fade(theme.cosmic().background.base.into()).into()),
                     border: Border {
                         radius: theme.cosmic().corner_radii.radius_m.into(),
                         width: 1.0,
-                        color: theme.cosmic().bg_divider().into(),
+                        color: fade(theme.cosmic().bg_divider().into()),
                     },
                     shadow: Shadow::default(),
-                    icon_color: Some(theme.cosmic().on_bg_color().into()),
+                    icon_color: Some(fade(theme.cosmic().on_bg_color().into())),
                 }
             })))
             .center_x(Length::Fill);
         row![
-            mouse_area(
+            self.dismiss_area(
                 container(horizontal_space().width(Length::Fixed(1.0)))
                     .width(Length::Fill)
                     .height(Length::Fill)
-            )
-            .on_press(Message::Hide),
+            ),
             container(
                 column![
-                    mouse_area(
+                    self.dismiss_area(
                         container(vertical_space())
                             .width(Length::Fill)
-                            .height(Length::Fixed(16.0))
-                    )
-                    .on_press(Message::Hide),
+                            .height(Length::Fixed(16.0 + (1.0 - progress) * 24.0))
+                    ),
                     container(
                         mouse_area(window)
                             .on_release(Message::CloseContextMenu)
@@ -1367,24 +4469,22 @@ impl cosmic::Application for CosmicAppLibrary {
                     )
                     .width(Length::Shrink)
                     .height(Length::Shrink),
-                    mouse_area(
+                    self.dismiss_area(
                         container(vertical_space())
                             .width(Length::Fill)
                             .height(Length::Fill)
                     )
-                    .on_press(Message::Hide)
                 ]
                 .height(Length::Fill)
             )
-            .max_width(1200.0)
+            .max_width(MAX_WIDTH)
             .width(Length::Shrink)
             .height(Length::Fill),
-            mouse_area(
+            self.dismiss_area(
                 container(horizontal_space().width(Length::Fixed(1.0)))
                     .width(Length::Fill)
                     .height(Length::Fill)
-            )
-            .on_press(Message::Hide),
+            ),
         ]
         .width(Length::Fill)
         .height(Length::Fill)
@@ -1392,59 +4492,205 @@ impl cosmic::Application for CosmicAppLibrary {
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        Subscription::batch(
-            vec![
-                desktop_files(0).map(|_| Message::LoadApps),
-                listen_with(|e, _status, _| match e {
-                    cosmic::iced::Event::PlatformSpecific(PlatformSpecific::Wayland(
-                        wayland::Event::Layer(e, _, id),
-                    )) => Some(Message::Layer(e, id)),
-                    cosmic::iced::Event::Keyboard(cosmic::iced::keyboard::Event::KeyReleased {
-                        key: Key::Named(Named::Escape),
-                        modifiers: _mods,
-                        ..
-                    }) => Some(Message::Hide),
-                    _ => None,
-                }),
-                self.core
-                    .watch_config::<cosmic_app_list_config::AppListConfig>(
-                        cosmic_app_list_config::APP_ID,
-                    )
-                    .map(|config| Message::AppListConfig(config.config)),
-            ]
-            .into_iter(),
-        )
+        let mut subscriptions = vec![
+            desktop_files(0).map(|_| Message::Refresh),
+            status::status(1).map(Message::Status),
+            listen_with(|e, status, id| match e {
+                cosmic::iced::Event::PlatformSpecific(PlatformSpecific::Wayland(
+                    wayland::Event::Layer(e, _, id),
+                )) => Some(Message::Layer(e, id)),
+                cosmic::iced::Event::PlatformSpecific(PlatformSpecific::Wayland(
+                    wayland::Event::Popup(PopupEvent::Done, _, id),
+                )) => Some(Message::PopupDone(id)),
+                cosmic::iced::Event::Keyboard(cosmic::iced::keyboard::Event::KeyReleased {
+                    key: Key::Named(Named::Escape),
+                    modifiers: _mods,
+                    ..
+                }) => Some(Message::Escape),
+                cosmic::iced::Event::Mouse(mouse::Event::ButtonPressed(
+                    mouse::Button::Back | mouse::Button::Other(8),
+                )) => Some(Message::NavigateBack),
+                cosmic::iced::Event::Mouse(mouse::Event::ButtonPressed(_)) => {
+                    Some(Message::PointerPressed)
+                }
+                // keys typed into an input or one of the dialogs are theirs
+                cosmic::iced::Event::Keyboard(cosmic::iced::keyboard::Event::KeyPressed {
+                    key,
+                    modifiers,
+                    ..
+                }) if status == event::Status::Ignored && id == *WINDOW_ID => {
+                    Some(Message::Key(key, modifiers))
+                }
+                cosmic::iced::Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                    Some(Message::Wheel(delta))
+                }
+                cosmic::iced::Event::Keyboard(cosmic::iced::keyboard::Event::ModifiersChanged(
+                    modifiers,
+                )) => Some(Message::Modifiers(modifiers)),
+                cosmic::iced::Event::Touch(e) => Some(Message::Touch(e)),
+                cosmic::iced::Event::Window(iced::window::Event::Resized(size)) => {
+                    Some(Message::Resized(id, size.width))
+                }
+                _ => None,
+            }),
+            self.core
+                .watch_config::<cosmic_app_list_config::AppListConfig>(
+                    cosmic_app_list_config::APP_ID,
+                )
+                .map(|config| Message::AppListConfig(config.config)),
+            // with the same version as its helper
+            cosmic::cosmic_config::config_subscription::<_, AppLibraryConfig>(
+                2,
+                Self::APP_ID.into(),
+                AppLibraryConfig::version(),
+            )
+            .map(|update| {
+                for err in update.errors {
+                    error!("{:?}", err);
+                }
+                Message::LibraryConfig(update.config)
+            }),
+        ];
+        if self.anim_start.is_some() {
+            subscriptions.push(iced::window::frames().map(Message::Tick));
+        }
+        Subscription::batch(subscriptions)
     }
 
     fn core_mut(&mut self) -> &mut Core {
         &mut self.core
     }
 
-    fn init(core: Core, _flags: Args) -> (Self, iced::Task<cosmic::app::Message<Self::Message>>) {
+    fn init(core: Core, flags: Args) -> (Self, iced::Task<cosmic::app::Message<Self::Message>>) {
         let helper = AppLibraryConfig::helper();
 
         let mut config: AppLibraryConfig = helper
             .as_ref()
             .map(|helper| {
-                AppLibraryConfig::get_entry(helper).unwrap_or_else(|(errors, config)| {
+                AppLibraryConfig::get_entry(helper).unwrap_or_else(|(errors, mut config)| {
                     for err in errors {
                         error!("{:?}", err);
                     }
+                    // a malformed group shouldn't lose the others
+                    config.recover_groups(helper);
                     config
                 })
             })
             .unwrap_or_default();
-        config.groups.sort();
 
-        let self_ = Self {
+        let state_helper = AppLibraryState::helper();
+        let state = state_helper
+            .as_ref()
+            .map(|helper| {
+                AppLibraryState::get_entry(helper).unwrap_or_else(|(errors, state)| {
+                    for err in errors {
+                        error!("{:?}", err);
+                    }
+                    state
+                })
+            })
+            .unwrap_or_default();
+        config.sort_groups(&state);
+
+        let mut self_ = Self {
             locale: current_locale::current_locale().ok(),
+            key_binds: key_bind::key_binds(&config.key_binds),
             config,
             core,
             helper,
+            state_helper,
+            state,
             last_hide: None,
             ..Default::default()
         };
 
+        // started to reset the library, or to search rather than to wait for being activated
+        if matches!(
+            flags.subcommand,
+            Some(LauncherCommands::Reset { yes: true })
+        ) {
+            let task = self_.reset();
+            return (self_, task);
+        }
+        if matches!(flags.subcommand, Some(LauncherCommands::Search)) {
+            self_.search_only = true;
+            let task = self_.activate();
+            return (self_, task);
+        }
+
         (self_, Task::none())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn scroll_offset_fits_fewer_results() {
+        let apps = |count: usize| -> Vec<_> {
+            (0..count)
+                .map(|i| {
+                    Arc::new(DesktopEntryData {
+                        id: format!("com.example.App{i}"),
+                        ..Default::default()
+                    })
+                })
+                .collect()
+        };
+        for two_line_names in [false, true] {
+            let mut app = CosmicAppLibrary::default();
+            app.config.two_line_names = two_line_names;
+            _ = cosmic::Application::update(
+                &mut app,
+                Message::FilterApps(String::new(), apps(200), 200),
+            );
+            app.scroll_offset = max_scroll_offset(200, app.app_row_height());
+            assert!(app.scroll_offset > 0.0);
+
+            // 2 apps fit in the grid without scrolling
+            _ = cosmic::Application::update(
+                &mut app,
+                Message::FilterApps(String::new(), apps(2), 2),
+            );
+            assert_eq!(app.scroll_offset, 0.0);
+            assert_eq!(app.scroll_relative, Some(0.0));
+        }
+    }
+
+    #[test]
+    fn user_entries_shadow_system_entries() {
+        let dir = std::env::temp_dir().join(format!("cosmic-app-library-{}", std::process::id()));
+        let write_entry = |data_dir: &str, id: &str, name: &str| {
+            let applications = dir.join(data_dir).join("applications");
+            fs::create_dir_all(&applications).unwrap();
+            fs::write(
+                applications.join(format!("{id}.desktop")),
+                format!("[Desktop Entry]\nType=Application\nName={name}\nExec=true\n"),
+            )
+            .unwrap();
+        };
+        write_entry("user", "com.example.Foo", "Foo (user)");
+        write_entry("system", "com.example.Foo", "Foo (system)");
+        write_entry("system", "com.example.Bar", "Bar");
+        std::env::set_var("XDG_DATA_HOME", dir.join("user"));
+        std::env::set_var("XDG_DATA_DIRS", dir.join("system"));
+
+        let (entries, _, _) = load_entries(None, false);
+        _ = fs::remove_dir_all(&dir);
+        let loaded: Vec<_> = entries
+            .iter()
+            .filter(|entry| entry.id.starts_with("com.example."))
+            .map(|entry| (entry.id.as_str(), entry.name.as_str()))
+            .collect();
+        assert_eq!(
+            loaded,
+            [
+                ("com.example.Bar", "Bar"),
+                ("com.example.Foo", "Foo (user)")
+            ]
+        );
+    }
+}
@@ -1,22 +1,73 @@
-use std::{sync::Arc, vec};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::Arc,
+    vec,
+};
 
 use cosmic::{
-    cosmic_config::{self, cosmic_config_derive::CosmicConfigEntry, CosmicConfigEntry},
+    cosmic_config::{self, cosmic_config_derive::CosmicConfigEntry, ConfigGet, CosmicConfigEntry},
     desktop::DesktopEntryData,
+    iced::Color,
 };
+use log::error;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 
-use crate::{config::APP_ID, fl};
+use crate::{app_state::AppLibraryState, config::APP_ID, fl, key_bind::Action};
+
+/// The accent colors of the theme, by the names a group's accent can use instead of a hex color.
+pub const THEME_ACCENTS: &[&str] = &[
+    "red", "orange", "yellow", "green", "blue", "indigo", "purple", "pink",
+];
+
+/// The color of an accent, which is a hex color or one of the [`THEME_ACCENTS`].
+pub fn parse_accent(accent: &str) -> Option<Color> {
+    let theme = cosmic::theme::active();
+    let palette = &theme.cosmic().palette;
+    let color = match accent.to_lowercase().as_str() {
+        "red" => palette.accent_red,
+        "orange" => palette.accent_orange,
+        "yellow" => palette.accent_yellow,
+        "green" => palette.accent_green,
+        "blue" => palette.accent_blue,
+        "indigo" => palette.accent_indigo,
+        "purple" => palette.accent_purple,
+        "pink" => palette.accent_pink,
+        _ => return Color::parse(accent),
+    };
+    Some(Color::from(color))
+}
 
 static HOME: Lazy<[AppGroup; 1]> = Lazy::new(|| {
     [AppGroup {
         name: "cosmic-library-home".to_string(),
         icon: "user-home-symbolic".to_string(),
         filter: FilterType::None,
+        accent: None,
+        sort: SortOrder::Alphabetical,
+        order: Vec::new(),
     }]
 });
 
+static FAVORITES: Lazy<AppGroup> = Lazy::new(|| AppGroup {
+    name: "cosmic-library-favorites".to_string(),
+    icon: "starred-symbolic".to_string(),
+    filter: FilterType::AppIds(Vec::new()),
+    accent: None,
+    sort: SortOrder::Alphabetical,
+    order: Vec::new(),
+});
+
+static HISTORY: Lazy<AppGroup> = Lazy::new(|| AppGroup {
+    name: "cosmic-library-history".to_string(),
+    icon: "document-open-recent-symbolic".to_string(),
+    filter: FilterType::AppIds(Vec::new()),
+    accent: None,
+    sort: SortOrder::Alphabetical,
+    order: Vec::new(),
+});
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum FilterType {
     /// A list of application IDs to include in the group.
@@ -27,12 +78,37 @@ pub enum FilterType {
         exclude: Vec<String>,
         /// The ID of applications which should be excluded from the results.
         include: Vec<String>,
+        /// Whether an application must have all of the categories, rather than any of them.
+        #[serde(default)]
+        match_all: bool,
+        /// Categories which exclude an application, even if it matches the other categories.
+        #[serde(default)]
+        exclude_categories: Vec<String>,
     },
     /// No filter is applied.
     /// This is intended for use with Home.
     None,
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    #[default]
+    Alphabetical,
+    /// The most frequently and recently launched apps first.
+    Frecency,
+    /// The order the apps were arranged in, with other apps alphabetically after them.
+    Manual,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GroupOrder {
+    /// Groups of apps first, then groups of categories, each alphabetically.
+    #[default]
+    Default,
+    /// The groups with the most recently launched apps first.
+    RecentlyUsed,
+}
+
 impl Default for FilterType {
     fn default() -> Self {
         FilterType::AppIds(Vec::new())
@@ -65,12 +141,72 @@ impl PartialOrd for FilterType {
     }
 }
 
+/// A search typed by the user.
+///
+/// `name:`, `cat:` and `id:` prefixes restrict the search to one field,
+/// and a leading `=` matches names exactly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchQuery {
+    /// Matches the name, a category, or the comment.
+    Any(String),
+    Name(String),
+    Category(String),
+    Id(String),
+    ExactName(String),
+}
+
+impl SearchQuery {
+    pub fn parse(input: &str) -> Self {
+        let input = input.trim().to_lowercase();
+        if let Some(name) = input.strip_prefix('=') {
+            return SearchQuery::ExactName(name.trim().to_string());
+        }
+        match input.split_once(':') {
+            Some(("name", name)) => SearchQuery::Name(name.trim().to_string()),
+            Some(("cat", category)) => SearchQuery::Category(category.trim().to_string()),
+            Some(("id", id)) => SearchQuery::Id(id.trim().to_string()),
+            _ => SearchQuery::Any(input),
+        }
+    }
+
+    /// Whether the entry matches, given the lowercased comments of the entries by ID.
+    pub fn matches(&self, entry: &DesktopEntryData, comments: &HashMap<String, String>) -> bool {
+        let has_category = |category: &str| {
+            entry
+                .categories
+                .iter()
+                .any(|acat| acat.to_lowercase() == category)
+        };
+        match self {
+            SearchQuery::Any(query) => {
+                entry.name.to_lowercase().contains(query)
+                    || has_category(query)
+                    || comments
+                        .get(&entry.id)
+                        .is_some_and(|comment| comment.contains(query))
+            }
+            SearchQuery::Name(name) => entry.name.to_lowercase().contains(name),
+            SearchQuery::Category(category) => has_category(category),
+            SearchQuery::Id(id) => entry.id.to_lowercase().contains(id),
+            SearchQuery::ExactName(name) => entry.name.to_lowercase() == *name,
+        }
+    }
+}
+
 // Object holding the state
 #[derive(Default, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct AppGroup {
     pub name: String,
     pub icon: String,
     pub filter: FilterType,
+    /// A hex color, or the name of an accent color of the theme like `blue`, used to highlight the group.
+    #[serde(default)]
+    pub accent: Option<String>,
+    #[serde(default)]
+    pub sort: SortOrder,
+    /// The IDs of apps in the order they were arranged in, for [`SortOrder::Manual`].
+    #[serde(default)]
+    pub order: Vec<String>,
     // pub popup: bool,
 }
 
@@ -97,7 +233,9 @@ impl AppGroup {
         input_value: &str,
         exceptions: &[Self],
         all_entries: &[Arc<DesktopEntryData>],
+        comments: &HashMap<String, String>,
     ) -> Vec<Arc<DesktopEntryData>> {
+        let query = SearchQuery::parse(input_value);
         all_entries
             .iter()
             .filter(|de| {
@@ -105,11 +243,7 @@ impl AppGroup {
                 keep_de &= if input_value.is_empty() {
                     !exceptions.iter().any(|x| x.matches(de))
                 } else {
-                    de.name.to_lowercase().contains(&input_value.to_lowercase())
-                        || de
-                            .categories
-                            .iter()
-                            .any(|acat| acat.to_lowercase() == input_value.to_lowercase())
+                    query.matches(de, comments)
                 };
                 keep_de
             })
@@ -117,30 +251,65 @@ impl AppGroup {
             .collect()
     }
 
-    fn matches(&self, entry: &DesktopEntryData) -> bool {
+    pub fn sort(&self, apps: &mut [Arc<DesktopEntryData>], state: &AppLibraryState) {
+        apps.sort_by(|a, b| a.name.cmp(&b.name));
+        match self.sort {
+            SortOrder::Alphabetical => {}
+            SortOrder::Frecency => {
+                apps.sort_by(|a, b| state.frecency(&b.id).total_cmp(&state.frecency(&a.id)));
+            }
+            SortOrder::Manual => {
+                apps.sort_by_key(|app| {
+                    self.order
+                        .iter()
+                        .position(|id| id == &app.id)
+                        .unwrap_or(usize::MAX)
+                });
+            }
+        }
+    }
+
+    pub fn matches(&self, entry: &DesktopEntryData) -> bool {
         match &self.filter {
             FilterType::AppIds(names) => names.iter().any(|id| id == &entry.id),
             FilterType::Categories {
                 categories,
                 include,
                 exclude,
-                ..
+                match_all,
+                exclude_categories,
             } => {
-                categories.iter().any(|cat| {
+                let has_category = |cat: &String| {
                     entry
                         .categories
                         .iter()
                         .any(|acat| acat.to_lowercase() == cat.to_lowercase())
-                }) && exclude.iter().all(|id| id != &entry.id)
+                };
+                let matches_categories = if *match_all {
+                    !categories.is_empty() && categories.iter().all(has_category)
+                } else {
+                    categories.iter().any(has_category)
+                };
+                matches_categories
+                    && !exclude_categories.iter().any(has_category)
+                    && exclude.iter().all(|id| id != &entry.id)
                     || include.iter().any(|id| id == &entry.id)
             }
             FilterType::None => true,
         }
     }
 
+    pub fn accent_color(&self) -> Option<Color> {
+        self.accent.as_deref().and_then(parse_accent)
+    }
+
     pub fn name(&self) -> String {
         if &self.name == "cosmic-library-home" {
             fl!("cosmic-library-home")
+        } else if &self.name == "cosmic-library-favorites" {
+            fl!("cosmic-library-favorites")
+        } else if &self.name == "cosmic-library-history" {
+            fl!("cosmic-library-history")
         } else if &self.name == "cosmic-office" {
             fl!("cosmic-office")
         } else if &self.name == "cosmic-system" {
@@ -156,6 +325,60 @@ impl AppGroup {
 #[derive(Debug, Clone, Serialize, Deserialize, CosmicConfigEntry)]
 pub struct AppLibraryConfig {
     pub(crate) groups: Vec<AppGroup>,
+    /// Launch the only result of a search when a space is typed after it.
+    pub(crate) launch_unique_result: bool,
+    /// Show only one tile for apps installed from several sources.
+    pub(crate) collapse_duplicates: bool,
+    /// The sources to prefer when collapsing duplicates, most preferred first.
+    pub(crate) duplicate_source_order: Vec<String>,
+    /// Switch to the neighbouring group when scrolling past the ends of the apps, or sideways.
+    pub(crate) scroll_switches_groups: bool,
+    /// Environment variables to set when launching an app, by app ID.
+    pub(crate) launch_env: HashMap<String, Vec<(String, String)>>,
+    /// Show entries marked `NoDisplay` or `Hidden`, which are normally left out.
+    pub(crate) show_hidden_desktop_entries: bool,
+    /// Show a History group with the most recently launched apps, after the other groups.
+    pub(crate) show_history: bool,
+    /// Close the library when clicking outside of it.
+    pub(crate) click_outside_to_close: bool,
+    /// Show smaller group tiles, or decide by the number of groups if unset.
+    pub(crate) compact_groups: Option<bool>,
+    /// Wrap app names over two lines, rather than shortening them to fit one.
+    pub(crate) two_line_names: bool,
+    /// The most apps to show for a search, or all of them if unset. Groups always show all of their apps.
+    pub(crate) max_results: Option<usize>,
+    /// Wrap around to the other end of the apps when moving past the first or last row with the keyboard,
+    /// rather than leaving them for the search or the groups.
+    pub(crate) nav_wrap: bool,
+    /// Show where every app was installed from, not only apps installed from several sources.
+    pub(crate) always_show_source: bool,
+    /// Give group tiles a background, so they read differently from app tiles.
+    pub(crate) distinct_folders: bool,
+    /// The order of the groups between Library Home and History.
+    pub(crate) group_order: GroupOrder,
+    /// Show only the groups in Library Home until something is searched, instead of all apps.
+    pub(crate) folders_first: bool,
+    /// Keep the search when the library is closed, to continue it when it is opened again.
+    pub(crate) remember_search: bool,
+    /// The IDs of apps starred in the library, which are shown in a Favorites group.
+    /// Unlike pinning, this doesn't touch the app tray.
+    pub(crate) favorites: Vec<String>,
+    /// How far an app has to be dragged before it is moved, in logical pixels.
+    /// If unset, the default depends on whether a touchscreen or a pointer is used.
+    pub(crate) drag_threshold: Option<f32>,
+    /// Don't let apps be dragged or dropped, so they are only organized through their menus.
+    pub(crate) disable_drag_and_drop: bool,
+    /// Keyboard shortcuts which replace or turn off the default ones, like
+    /// `{"Ctrl+Tab": None, "Ctrl+n": Some(NextGroup)}`.
+    pub(crate) key_binds: BTreeMap<String, Option<Action>>,
+    /// Open the library as just a search box, which shows the apps once something is typed.
+    pub(crate) search_only: bool,
+    /// Categories of apps which Library Home doesn't list, like `Settings` or `Screensaver`.
+    /// Searching still finds them.
+    pub(crate) home_exclude_categories: Vec<String>,
+    /// Of launchers which only differ by a language suffix, like `manual-fr` and `manual-de`,
+    /// show just the one in the language of the session.
+    pub(crate) session_language_only: bool,
 }
 
 impl AppLibraryConfig {
@@ -167,13 +390,71 @@ impl AppLibraryConfig {
         cosmic_config::Config::new(APP_ID, Self::version()).ok()
     }
 
-    pub fn add(&mut self, name: String) {
+    /// The directory the config is stored in.
+    fn dir() -> Option<PathBuf> {
+        xdg::BaseDirectories::new().ok().map(|dirs| {
+            dirs.get_config_home()
+                .join("cosmic")
+                .join(APP_ID)
+                .join(format!("v{}", Self::version()))
+        })
+    }
+
+    /// Copies the stored config to a `.bak` directory next to it, to be restored by hand.
+    pub fn backup() {
+        if let Some(dir) = Self::dir() {
+            if let Err(err) = backup_dir(&dir) {
+                error!("{:?}", err);
+            }
+        }
+    }
+
+    /// Keeps the groups which can still be read when others are malformed, if the stored groups
+    /// fail to parse, after backing them up, since they would be overwritten on the next write.
+    pub fn recover_groups(&mut self, helper: &cosmic_config::Config) {
+        // other keys failing, or the groups not being stored, leave the groups as they are
+        if !matches!(
+            helper.get::<Vec<AppGroup>>("groups"),
+            Err(cosmic_config::Error::Ron(_) | cosmic_config::Error::RonSpanned(_))
+        ) {
+            return;
+        }
+        let Some(path) = Self::dir().map(|dir| dir.join("groups")) else {
+            return;
+        };
+        let Ok(stored) = std::fs::read_to_string(&path) else {
+            return;
+        };
+        if let Err(err) = std::fs::copy(&path, path.with_extension("bak")) {
+            error!("{:?}", err);
+        }
+
+        let groups = readable_groups(&stored);
+        if !groups.is_empty() {
+            self.groups = groups;
+        }
+    }
+
+    pub fn add(&mut self, name: String, state: &AppLibraryState) {
         self.groups.push(AppGroup {
             name,
             icon: "folder-symbolic".to_string(),
             filter: FilterType::AppIds(Vec::new()),
+            accent: None,
+            sort: SortOrder::Alphabetical,
+            order: Vec::new(),
         });
+        self.sort_groups(state);
+    }
+
+    /// Sorts the groups in the configured order.
+    pub fn sort_groups(&mut self, state: &AppLibraryState) {
         self.groups.sort();
+        if self.group_order == GroupOrder::RecentlyUsed {
+            // stable, so groups which weren't used keep the default order
+            self.groups
+                .sort_by_key(|g| std::cmp::Reverse(state.group_use.get(&g.name).copied()));
+        }
     }
 
     pub fn remove(&mut self, i: usize) {
@@ -188,8 +469,68 @@ impl AppLibraryConfig {
         }
     }
 
-    pub fn remove_entry(&mut self, i: usize, id: &str) {
+    pub fn set_accent(&mut self, i: usize, accent: Option<String>) {
+        if let Some(group) = self.groups.get_mut(i - 1) {
+            group.accent = accent;
+        }
+    }
+
+    pub fn set_icon(&mut self, i: usize, icon: String) {
+        if let Some(group) = self.groups.get_mut(i - 1) {
+            group.icon = icon;
+        }
+    }
+
+    pub fn set_sort(&mut self, i: usize, sort: SortOrder) {
         if let Some(group) = self.groups.get_mut(i - 1) {
+            group.sort = sort;
+        }
+    }
+
+    pub fn set_order(&mut self, i: usize, order: Vec<String>) {
+        if let Some(group) = self.groups.get_mut(i - 1) {
+            group.order = order;
+        }
+    }
+
+    pub fn filter(&self, i: usize) -> Option<&FilterType> {
+        self.groups.get(i - 1).map(|g| &g.filter)
+    }
+
+    /// Replaces the filter of a group, returning the new index of the group after sorting.
+    pub fn set_filter(
+        &mut self,
+        i: usize,
+        filter: FilterType,
+        state: &AppLibraryState,
+    ) -> Option<usize> {
+        let group = self.groups.get_mut(i - 1)?;
+        group.filter = match filter {
+            // a category filter without categories is just a list of apps
+            FilterType::Categories {
+                categories,
+                include,
+                ..
+            } if categories.is_empty() => FilterType::AppIds(include),
+            filter => filter,
+        };
+        let group = group.clone();
+        self.sort_groups(state);
+        self.groups.iter().position(|g| g == &group).map(|i| i + 1)
+    }
+
+    /// Removes the app from group `i` only, leaving it in any other groups. Groups filled by
+    /// category exclude it, so it isn't matched again, and Favorites unstars it.
+    /// Library Home and History are filled by the library, so removing from them does nothing.
+    pub fn remove_entry(&mut self, i: usize, id: &str) {
+        if i == 0 || self.is_history(i) {
+            return;
+        }
+        if self.is_favorites(i) {
+            self.favorites.retain(|f| f != id);
+            return;
+        }
+        if let Some(group) = i.checked_sub(1).and_then(|i| self.groups.get_mut(i)) {
             match &mut group.filter {
                 FilterType::AppIds(ids) => ids.retain(|conf_id| conf_id != id),
                 FilterType::Categories {
@@ -202,15 +543,14 @@ impl AppLibraryConfig {
                 FilterType::None => {}
             }
         }
-        if i - 1 < self.groups.len() {
-            if let FilterType::AppIds(ids) = &mut self.groups[i - 1].filter {
-                ids.retain(|x| x != id);
-            }
-        }
     }
 
+    /// Adds the app to group `i`, leaving its other groups untouched,
+    /// or removes it from every group when `i` is Home.
     pub fn add_entry(&mut self, i: usize, id: &str) {
-        if i - 1 < self.groups.len() {
+        if self.is_favorites(i) {
+            self.toggle_favorite(id, true);
+        } else if i > 0 && i - 1 < self.groups.len() {
             if let FilterType::AppIds(ids) = &mut self.groups[i - 1].filter {
                 if ids.iter().all(|s| s != id) {
                     ids.push(id.to_string());
@@ -223,7 +563,7 @@ impl AppLibraryConfig {
                 exclude.retain(|conf_id| conf_id != id);
                 include.push(id.to_string());
             }
-        } else {
+        } else if i == 0 {
             // add to filter of all groups, forcing it to the Home group
             for group in &mut self.groups {
                 match &mut group.filter {
@@ -231,9 +571,7 @@ impl AppLibraryConfig {
                         ids.retain(|conf_id| conf_id != id);
                     }
                     FilterType::Categories {
-                        categories: _,
-                        exclude,
-                        include,
+                        exclude, include, ..
                     } => {
                         include.retain(|conf_id| conf_id != id);
                         if exclude.iter().all(|conf_id| conf_id != id) {
@@ -247,7 +585,43 @@ impl AppLibraryConfig {
     }
 
     pub fn groups(&self) -> Vec<&AppGroup> {
-        HOME.iter().chain(&self.groups).collect()
+        HOME.iter()
+            .chain(&self.groups)
+            .chain((!self.favorites.is_empty()).then(|| &*FAVORITES))
+            .chain(self.show_history.then(|| &*HISTORY))
+            .collect()
+    }
+
+    /// Whether group `i` is the Favorites group, which is shown while any app is starred.
+    pub fn is_favorites(&self, i: usize) -> bool {
+        !self.favorites.is_empty() && i == self.groups.len() + 1
+    }
+
+    /// Whether group `i` is the History group, which can't be edited.
+    pub fn is_history(&self, i: usize) -> bool {
+        self.show_history && i == self.groups.len() + 1 + usize::from(!self.favorites.is_empty())
+    }
+
+    /// Whether group `i` is filled by the library rather than configured, so it can't be edited.
+    pub fn is_virtual(&self, i: usize) -> bool {
+        self.is_favorites(i) || self.is_history(i)
+    }
+
+    /// Whether the app is in group `i`. Library Home and History aren't groups apps are added to,
+    /// so they contain none.
+    pub fn contains(&self, i: usize, entry: &DesktopEntryData) -> bool {
+        if self.is_favorites(i) {
+            return self.favorites.contains(&entry.id);
+        }
+        i > 0 && !self.is_history(i) && self.groups().get(i).is_some_and(|g| g.matches(entry))
+    }
+
+    /// Stars or unstars an app.
+    pub fn toggle_favorite(&mut self, id: &str, favorite: bool) {
+        self.favorites.retain(|f| f != id);
+        if favorite {
+            self.favorites.push(id.to_string());
+        }
     }
 
     pub fn filtered(
@@ -255,12 +629,48 @@ impl AppLibraryConfig {
         i: usize,
         input_value: &str,
         entries: &Vec<Arc<DesktopEntryData>>,
+        comments: &HashMap<String, String>,
+        state: &AppLibraryState,
     ) -> Vec<Arc<DesktopEntryData>> {
-        if i == 0 {
-            HOME[0].filtered(input_value, &self.groups, entries)
+        let ids = if self.is_favorites(i) {
+            Some(&self.favorites)
+        } else if self.is_history(i) {
+            Some(&state.history)
+        } else {
+            None
+        };
+        if let Some(ids) = ids {
+            let query = SearchQuery::parse(input_value);
+            return ids
+                .iter()
+                .filter_map(|id| entries.iter().find(|e| &e.id == id))
+                .filter(|e| input_value.is_empty() || query.matches(e, comments))
+                .cloned()
+                .collect();
+        }
+        let mut apps = if i == 0 {
+            let mut apps = HOME[0].filtered(input_value, &self.groups, entries, comments);
+            if input_value.is_empty() && !self.home_exclude_categories.is_empty() {
+                apps.retain(|app| {
+                    !app.categories.iter().any(|category| {
+                        self.home_exclude_categories
+                            .iter()
+                            .any(|exclude| exclude.eq_ignore_ascii_case(category))
+                    })
+                });
+            }
+            apps
         } else {
-            self._filtered(i - 1, input_value, entries)
+            self._filtered(i - 1, input_value, entries, comments)
+        };
+        if let Some(group) = self.groups().get(i) {
+            group.sort(&mut apps, state);
+        }
+        if let SearchQuery::Any(query) = SearchQuery::parse(input_value) {
+            // apps matching by name come before those matching only by category or comment
+            apps.sort_by_key(|app| !app.name.to_lowercase().contains(&query));
         }
+        apps
     }
 
     pub fn _filtered(
@@ -268,14 +678,81 @@ impl AppLibraryConfig {
         i: usize,
         input_value: &str,
         entries: &Vec<Arc<DesktopEntryData>>,
+        comments: &HashMap<String, String>,
     ) -> Vec<Arc<DesktopEntryData>> {
         self.groups
             .get(i)
-            .map(|g| g.filtered(input_value, &Vec::new(), entries))
+            .map(|g| g.filtered(input_value, &Vec::new(), entries, comments))
             .unwrap_or_default()
     }
 }
 
+/// Copies the entries stored in a directory to a `.bak` directory next to it,
+/// replacing an older backup.
+pub fn backup_dir(dir: &Path) -> std::io::Result<()> {
+    let backup = dir.with_extension("bak");
+    if backup.exists() {
+        std::fs::remove_dir_all(&backup)?;
+    }
+    std::fs::create_dir_all(&backup)?;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            std::fs::copy(entry.path(), backup.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// The groups of a stored RON list which can be parsed, skipping malformed ones.
+fn readable_groups(list: &str) -> Vec<AppGroup> {
+    let mut groups = Vec::new();
+    for item in split_ron_list(list) {
+        match ron::from_str::<AppGroup>(item) {
+            Ok(group) => groups.push(group),
+            Err(err) => error!("skipping malformed group: {:?}", err),
+        }
+    }
+    groups
+}
+
+/// Splits the items of a RON list, without parsing them.
+fn split_ron_list(list: &str) -> Vec<&str> {
+    let Some(inner) = list
+        .trim()
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+    else {
+        return Vec::new();
+    };
+    let mut items = Vec::new();
+    let (mut depth, mut in_string, mut escaped, mut start) = (0usize, false, false, 0);
+    for (i, c) in inner.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                items.push(inner[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(inner[start..].trim());
+    items.retain(|item| !item.is_empty());
+    items
+}
+
 impl Default for AppLibraryConfig {
     fn default() -> Self {
         AppLibraryConfig {
@@ -292,7 +769,12 @@ impl Default for AppLibraryConfig {
                             "thunderbird".to_string(),
                         ],
                         exclude: Vec::new(),
+                        match_all: false,
+                        exclude_categories: Vec::new(),
                     },
+                    accent: None,
+                    sort: SortOrder::Alphabetical,
+                    order: Vec::new(),
                 },
                 AppGroup {
                     name: "cosmic-system".to_string(),
@@ -309,7 +791,12 @@ impl Default for AppLibraryConfig {
                             "com.system76.CosmicStore".to_string(),
                             "com.system76.CosmicTerm".to_string(),
                         ],
+                        match_all: false,
+                        exclude_categories: Vec::new(),
                     },
+                    accent: None,
+                    sort: SortOrder::Alphabetical,
+                    order: Vec::new(),
                 },
                 AppGroup {
                     name: "cosmic-utilities".to_string(),
@@ -321,9 +808,304 @@ impl Default for AppLibraryConfig {
                             "com.system76.CosmicEdit".to_string(),
                             "com.system76.CosmicFiles".to_string(),
                         ],
+                        match_all: false,
+                        exclude_categories: Vec::new(),
                     },
+                    accent: None,
+                    sort: SortOrder::Alphabetical,
+                    order: Vec::new(),
                 },
             ],
+            launch_unique_result: false,
+            collapse_duplicates: false,
+            duplicate_source_order: vec![
+                "system".to_string(),
+                "flatpak".to_string(),
+                "local".to_string(),
+                "snap".to_string(),
+                "nix".to_string(),
+            ],
+            scroll_switches_groups: false,
+            launch_env: HashMap::new(),
+            show_hidden_desktop_entries: false,
+            show_history: false,
+            click_outside_to_close: true,
+            compact_groups: None,
+            two_line_names: true,
+            max_results: None,
+            nav_wrap: false,
+            always_show_source: false,
+            distinct_folders: true,
+            group_order: GroupOrder::Default,
+            folders_first: false,
+            remember_search: false,
+            favorites: Vec::new(),
+            drag_threshold: None,
+            disable_drag_and_drop: false,
+            key_binds: BTreeMap::new(),
+            search_only: false,
+            home_exclude_categories: Vec::new(),
+            session_language_only: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, categories: &[&str]) -> DesktopEntryData {
+        DesktopEntryData {
+            id: id.to_string(),
+            name: id.to_string(),
+            categories: categories.iter().map(|c| c.to_string()).collect(),
+            ..Default::default()
         }
     }
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn categories_group(
+        categories: &[&str],
+        match_all: bool,
+        include: &[&str],
+        exclude: &[&str],
+    ) -> AppGroup {
+        AppGroup {
+            filter: FilterType::Categories {
+                categories: strings(categories),
+                exclude: strings(exclude),
+                include: strings(include),
+                match_all,
+                exclude_categories: Vec::new(),
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn match_any_category_with_overrides() {
+        let group = categories_group(&["Development", "Network"], false, &["editor"], &["curl"]);
+        assert!(group.matches(&entry("ide", &["Development"])));
+        assert!(group.matches(&entry("browser", &["Network"])));
+        assert!(!group.matches(&entry("game", &["Game"])));
+        // included without any of the categories
+        assert!(group.matches(&entry("editor", &["Utility"])));
+        // excluded despite having the categories
+        assert!(!group.matches(&entry("curl", &["Development", "Network"])));
+    }
+
+    #[test]
+    fn match_all_categories_with_overrides() {
+        let group = categories_group(&["Development", "Network"], true, &["editor"], &["curl"]);
+        assert!(group.matches(&entry("wireshark", &["Network", "Development"])));
+        assert!(!group.matches(&entry("ide", &["Development"])));
+        assert!(!group.matches(&entry("browser", &["Network"])));
+        // included with only some of the categories
+        assert!(group.matches(&entry("editor", &["Development"])));
+        // excluded despite having all of the categories
+        assert!(!group.matches(&entry("curl", &["Development", "Network"])));
+    }
+
+    #[test]
+    fn excluded_categories_override_categories() {
+        let mut group = categories_group(&["Utility"], false, &["screen-reader"], &[]);
+        if let FilterType::Categories {
+            exclude_categories, ..
+        } = &mut group.filter
+        {
+            *exclude_categories = strings(&["Accessibility"]);
+        }
+        assert!(group.matches(&entry("calculator", &["Utility"])));
+        assert!(!group.matches(&entry("magnifier", &["Utility", "Accessibility"])));
+        // included apps are kept even with an excluded category
+        assert!(group.matches(&entry("screen-reader", &["Utility", "Accessibility"])));
+    }
+
+    #[test]
+    fn match_all_without_categories_matches_only_included() {
+        let group = categories_group(&[], true, &["editor"], &[]);
+        assert!(!group.matches(&entry("ide", &["Development"])));
+        assert!(group.matches(&entry("editor", &[])));
+    }
+
+    #[test]
+    fn comment_matches_sort_after_name_matches() {
+        let mut deluge = entry("deluge", &["Network"]);
+        deluge.name = "Deluge".to_string();
+        let mut torrential = entry("torrential", &["Network"]);
+        torrential.name = "Torrential".to_string();
+        let mut editor = entry("editor", &["Utility"]);
+        editor.name = "Editor".to_string();
+        let entries = vec![Arc::new(deluge), Arc::new(editor), Arc::new(torrential)];
+        let comments = HashMap::from([("deluge".to_string(), "bittorrent client".to_string())]);
+
+        let apps = AppLibraryConfig::default().filtered(
+            0,
+            "torrent",
+            &entries,
+            &comments,
+            &AppLibraryState::default(),
+        );
+        let ids: Vec<_> = apps.iter().map(|app| app.id.as_str()).collect();
+        assert_eq!(ids, ["torrential", "deluge"]);
+    }
+
+    #[test]
+    fn parse_search_prefixes() {
+        assert_eq!(
+            SearchQuery::parse("name:Fire"),
+            SearchQuery::Name("fire".to_string())
+        );
+        assert_eq!(
+            SearchQuery::parse("cat: Game"),
+            SearchQuery::Category("game".to_string())
+        );
+        assert_eq!(
+            SearchQuery::parse("id:org.mozilla"),
+            SearchQuery::Id("org.mozilla".to_string())
+        );
+        assert_eq!(
+            SearchQuery::parse("=Firefox"),
+            SearchQuery::ExactName("firefox".to_string())
+        );
+        // unknown prefixes are searched as they are
+        assert_eq!(
+            SearchQuery::parse("foo:bar"),
+            SearchQuery::Any("foo:bar".to_string())
+        );
+        assert_eq!(
+            SearchQuery::parse("fire"),
+            SearchQuery::Any("fire".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_empty_search() {
+        assert_eq!(SearchQuery::parse(""), SearchQuery::Any(String::new()));
+        assert_eq!(
+            SearchQuery::parse("name:"),
+            SearchQuery::Name(String::new())
+        );
+        assert_eq!(
+            SearchQuery::parse("= "),
+            SearchQuery::ExactName(String::new())
+        );
+    }
+
+    #[test]
+    fn app_in_several_groups() {
+        let folder = |name: &str| AppGroup {
+            name: name.to_string(),
+            filter: FilterType::AppIds(Vec::new()),
+            ..Default::default()
+        };
+        let mut config = AppLibraryConfig {
+            groups: vec![
+                folder("Browsers"),
+                folder("Work"),
+                categories_group(&["Network"], false, &[], &[]),
+            ],
+            ..Default::default()
+        };
+        let firefox = entry("firefox", &["Network"]);
+        config.add_entry(1, "firefox");
+        config.add_entry(2, "firefox");
+        assert!((1..=3).all(|i| config.contains(i, &firefox)));
+
+        config.remove_entry(1, "firefox");
+        assert!(!config.contains(1, &firefox));
+        assert!(config.contains(2, &firefox));
+        assert!(config.contains(3, &firefox));
+
+        config.remove_entry(3, "firefox");
+        assert!(config.contains(2, &firefox));
+        assert!(!config.contains(3, &firefox));
+    }
+
+    #[test]
+    fn remove_from_virtual_groups() {
+        let mut config = AppLibraryConfig {
+            groups: vec![categories_group(&["Network"], false, &[], &[])],
+            favorites: strings(&["firefox"]),
+            show_history: true,
+            ..Default::default()
+        };
+        let firefox = entry("firefox", &["Network"]);
+        let unchanged = config.clone();
+
+        // library home and history
+        config.remove_entry(0, "firefox");
+        config.remove_entry(3, "firefox");
+        assert_eq!(config.groups, unchanged.groups);
+        assert_eq!(config.favorites, unchanged.favorites);
+
+        // favorites
+        assert!(config.contains(2, &firefox));
+        config.remove_entry(2, "firefox");
+        assert!(config.favorites.is_empty());
+        assert!(config.contains(1, &firefox));
+    }
+
+    #[test]
+    fn remove_from_real_groups() {
+        let mut config = AppLibraryConfig {
+            groups: vec![
+                categories_group(&["Network"], false, &[], &[]),
+                AppGroup {
+                    filter: FilterType::AppIds(strings(&["firefox"])),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let firefox = entry("firefox", &["Network"]);
+
+        config.remove_entry(1, "firefox");
+        assert!(!config.contains(1, &firefox));
+        assert!(matches!(
+            &config.groups[0].filter,
+            FilterType::Categories { exclude, .. } if *exclude == strings(&["firefox"])
+        ));
+
+        config.remove_entry(2, "firefox");
+        assert!(!config.contains(2, &firefox));
+        assert_eq!(config.groups[1].filter, FilterType::AppIds(Vec::new()));
+    }
+
+    #[test]
+    fn malformed_groups_are_skipped() {
+        let group = |name: &str| AppGroup {
+            name: name.to_string(),
+            filter: FilterType::AppIds(strings(&["firefox"])),
+            ..Default::default()
+        };
+        let stored = format!(
+            "[{}, (name: \"Broken\", icon: ), {}]",
+            ron::ser::to_string(&group("Office, (work) \"2\"")).unwrap(),
+            ron::ser::to_string(&group("Games")).unwrap(),
+        );
+        assert_eq!(
+            readable_groups(&stored),
+            [group("Office, (work) \"2\""), group("Games")]
+        );
+    }
+
+    #[test]
+    fn split_ron_list_keeps_strings_whole() {
+        let list = r#"[(name: "a, (b", ids: ["c)", "\"d,"]), (name: "\\"), ]"#;
+        assert_eq!(
+            split_ron_list(list),
+            [r#"(name: "a, (b", ids: ["c)", "\"d,"])"#, r#"(name: "\\")"#]
+        );
+    }
+
+    #[test]
+    fn split_empty_ron_list() {
+        assert!(split_ron_list("[]").is_empty());
+        assert!(split_ron_list(" [\n] \n").is_empty());
+        assert!(split_ron_list("").is_empty());
+    }
 }
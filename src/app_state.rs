@@ -0,0 +1,133 @@
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use cosmic::{
+    cosmic_config::{self, cosmic_config_derive::CosmicConfigEntry, CosmicConfigEntry},
+    desktop::DesktopEntryData,
+};
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_group::{backup_dir, AppGroup},
+    config::APP_ID,
+};
+
+const MAX_RECENT_SEARCHES: usize = 8;
+const MAX_HISTORY: usize = 20;
+const DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct AppUsage {
+    pub launches: u32,
+    /// Seconds since the Unix epoch.
+    pub last_launch: u64,
+}
+
+/// State which is remembered between sessions, but is not configuration.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, CosmicConfigEntry)]
+pub struct AppLibraryState {
+    /// The most recent searches, newest first.
+    pub(crate) recent_searches: Vec<String>,
+    /// How often and how recently each app was launched, by app ID.
+    pub(crate) usage: HashMap<String, AppUsage>,
+    /// The IDs of the most recently launched apps, newest first.
+    #[serde(default)]
+    pub(crate) history: Vec<String>,
+    /// When an app of each group was last launched, by group name, for
+    /// [`GroupOrder::RecentlyUsed`](crate::app_group::GroupOrder::RecentlyUsed).
+    #[serde(default)]
+    pub(crate) group_use: HashMap<String, u64>,
+}
+
+impl AppLibraryState {
+    pub fn version() -> u64 {
+        1
+    }
+
+    pub fn helper() -> Option<cosmic_config::Config> {
+        cosmic_config::Config::new_state(APP_ID, Self::version()).ok()
+    }
+
+    /// Copies the stored state to a `.bak` directory next to it, to be restored by hand.
+    pub fn backup() {
+        let Some(dir) = xdg::BaseDirectories::new().ok().map(|dirs| {
+            dirs.get_state_home()
+                .join("cosmic")
+                .join(APP_ID)
+                .join(format!("v{}", Self::version()))
+        }) else {
+            return;
+        };
+        if let Err(err) = backup_dir(&dir) {
+            error!("{:?}", err);
+        }
+    }
+
+    pub fn add_search(&mut self, search: &str) {
+        let search = search.trim();
+        if search.is_empty() {
+            return;
+        }
+        self.recent_searches.retain(|s| s != search);
+        self.recent_searches.insert(0, search.to_string());
+        self.recent_searches.truncate(MAX_RECENT_SEARCHES);
+    }
+
+    pub fn record_launch(&mut self, id: &str) {
+        let usage = self.usage.entry(id.to_string()).or_default();
+        usage.launches = usage.launches.saturating_add(1);
+        usage.last_launch = now();
+        self.history.retain(|h| h != id);
+        self.history.insert(0, id.to_string());
+        self.history.truncate(MAX_HISTORY);
+    }
+
+    /// Marks the groups containing an app as used now, returning whether any group contains it.
+    pub fn record_group_use(&mut self, groups: &[AppGroup], entry: &DesktopEntryData) -> bool {
+        let time = now();
+        // removed or renamed groups are forgotten
+        self.group_use
+            .retain(|name, _| groups.iter().any(|g| &g.name == name));
+        let mut used = false;
+        for group in groups.iter().filter(|g| g.matches(entry)) {
+            self.group_use.insert(group.name.clone(), time);
+            used = true;
+        }
+        used
+    }
+
+    /// How often an app was launched, and how many days ago it was last launched.
+    pub fn launches(&self, id: &str) -> Option<(u32, u64)> {
+        let usage = self.usage.get(id).filter(|usage| usage.launches > 0)?;
+        Some((
+            usage.launches,
+            now().saturating_sub(usage.last_launch) / DAY,
+        ))
+    }
+
+    /// Scores an app by how often it was launched, weighing recent launches more.
+    pub fn frecency(&self, id: &str) -> f32 {
+        let Some(usage) = self.usage.get(id) else {
+            return 0.0;
+        };
+        let age = now().saturating_sub(usage.last_launch);
+        let weight = match age {
+            a if a < 4 * DAY => 1.0,
+            a if a < 14 * DAY => 0.7,
+            a if a < 31 * DAY => 0.5,
+            a if a < 90 * DAY => 0.3,
+            _ => 0.1,
+        };
+        usage.launches as f32 * weight
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
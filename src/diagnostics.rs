@@ -0,0 +1,137 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use cosmic::widget::icon;
+use freedesktop_desktop_entry::DesktopEntry;
+
+/// The main categories of the freedesktop menu specification.
+/// An entry should have one of them to be sorted into a menu.
+const MAIN_CATEGORIES: &[&str] = &[
+    "AudioVideo",
+    "Audio",
+    "Video",
+    "Development",
+    "Education",
+    "Game",
+    "Graphics",
+    "Network",
+    "Office",
+    "Science",
+    "Settings",
+    "System",
+    "Utility",
+];
+
+/// Prints the problems with desktop entries which keep them from being shown,
+/// or make them show up differently than their developers might expect.
+pub fn validate() {
+    let xdg_current_desktop = std::env::var("XDG_CURRENT_DESKTOP").ok();
+    let mut seen: HashMap<String, PathBuf> = HashMap::new();
+    let mut count = 0;
+
+    // the default paths are in order of precedence
+    for dir in freedesktop_desktop_entry::default_paths() {
+        let mut files = Vec::new();
+        desktop_files(&dir, &mut files);
+        files.sort();
+        for path in files {
+            let id = desktop_id(&dir, &path);
+            let issues = match seen.get(&id).cloned() {
+                Some(shadowing) => vec![format!("shadowed by {}", shadowing.display())],
+                None => {
+                    seen.insert(id, path.clone());
+                    issues(&path, xdg_current_desktop.as_deref())
+                }
+            };
+            for issue in &issues {
+                println!("{}: {issue}", path.display());
+            }
+            count += issues.len();
+        }
+    }
+
+    if count == 0 {
+        println!("No problems found");
+    }
+}
+
+fn issues(path: &Path, xdg_current_desktop: Option<&str>) -> Vec<String> {
+    let entry = match DesktopEntry::from_path::<&str>(path, None) {
+        Ok(entry) => entry,
+        Err(err) => return vec![format!("could not be parsed: {err}")],
+    };
+    let mut issues = Vec::new();
+
+    if entry.exec().is_none() {
+        issues.push("has no Exec key, so it is not shown".to_string());
+    }
+    if entry.no_display() || entry.hidden() {
+        issues.push(
+            "is marked NoDisplay or Hidden, so it is only shown with show_hidden_desktop_entries"
+                .to_string(),
+        );
+    }
+    if let (Some(desktop), Some(only_show_in)) = (xdg_current_desktop, entry.only_show_in()) {
+        if !only_show_in.contains(&desktop) {
+            issues.push(format!(
+                "OnlyShowIn does not include {desktop}, so it is not shown"
+            ));
+        }
+    }
+
+    match entry.icon() {
+        None => issues.push("has no Icon, so a generic icon is shown".to_string()),
+        Some(name) if Path::new(name).is_absolute() => {
+            if !Path::new(name).exists() {
+                issues.push(format!("the icon file {name} does not exist"));
+            }
+        }
+        Some(name) => {
+            if icon::from_name(name).size(128).path().is_none() {
+                issues.push(format!(
+                    "the icon {name} is not in the icon theme, so a generic icon is shown"
+                ));
+            }
+        }
+    }
+
+    let categories = entry.categories().unwrap_or_default();
+    if categories.iter().all(|c| c.is_empty()) {
+        issues.push(
+            "has no Categories, so it only appears in Library Home and manual folders".to_string(),
+        );
+    } else if !categories.iter().any(|c| MAIN_CATEGORIES.contains(c)) {
+        issues.push(format!(
+            "none of the Categories {} is a main category",
+            categories.join(";")
+        ));
+    }
+
+    issues
+}
+
+/// Collects the desktop files under `dir`, recursively.
+fn desktop_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in read_dir.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            desktop_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "desktop") {
+            files.push(path);
+        }
+    }
+}
+
+/// The desktop file ID, which is the path relative to the data directory with `/` replaced by `-`.
+fn desktop_id(dir: &Path, path: &Path) -> String {
+    path.strip_prefix(dir)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('/', "-")
+}
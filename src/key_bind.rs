@@ -0,0 +1,170 @@
+//! The keyboard shortcuts of the library, which are both handled and listed in the help from here.
+
+use std::collections::BTreeMap;
+
+use cosmic::{
+    iced::keyboard::{key::Named, Key, Modifiers},
+    widget::menu::key_bind::{KeyBind, Modifier},
+};
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::fl;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    ToggleGroupFocus,
+    NextGroup,
+    PreviousGroup,
+    SelectGroup(usize),
+    ToggleHome,
+    Refresh,
+    ToggleHelp,
+}
+
+impl Action {
+    pub fn description(self) -> String {
+        match self {
+            Action::MoveLeft => fl!("move-left"),
+            Action::MoveRight => fl!("move-right"),
+            Action::MoveUp => fl!("move-up"),
+            Action::MoveDown => fl!("move-down"),
+            Action::ToggleGroupFocus => fl!("toggle-group-focus"),
+            Action::NextGroup => fl!("next-group"),
+            Action::PreviousGroup => fl!("previous-group"),
+            Action::SelectGroup(_) => fl!("select-group"),
+            Action::ToggleHome => fl!("toggle-home"),
+            Action::Refresh => fl!("refresh"),
+            Action::ToggleHelp => fl!("keyboard-shortcuts"),
+        }
+    }
+}
+
+/// The default key bindings, in the order they are listed in the help.
+fn default_key_binds() -> Vec<(KeyBind, Action)> {
+    let bind = |modifiers: &[Modifier], key: Key| KeyBind {
+        modifiers: modifiers.to_vec(),
+        key,
+    };
+    let mut key_binds = vec![
+        (bind(&[], Key::Named(Named::ArrowLeft)), Action::MoveLeft),
+        (bind(&[], Key::Named(Named::ArrowRight)), Action::MoveRight),
+        (bind(&[], Key::Named(Named::ArrowUp)), Action::MoveUp),
+        (bind(&[], Key::Named(Named::ArrowDown)), Action::MoveDown),
+        // not Tab, which moves the focus between the inputs
+        (bind(&[], Key::Named(Named::F6)), Action::ToggleGroupFocus),
+        (
+            bind(&[Modifier::Ctrl], Key::Named(Named::Tab)),
+            Action::NextGroup,
+        ),
+        (
+            bind(&[Modifier::Ctrl, Modifier::Shift], Key::Named(Named::Tab)),
+            Action::PreviousGroup,
+        ),
+    ];
+    for n in 1..=9 {
+        key_binds.push((
+            bind(&[Modifier::Ctrl], Key::Character(n.to_string().into())),
+            Action::SelectGroup(n - 1),
+        ));
+    }
+    key_binds.extend([
+        (
+            bind(&[Modifier::Alt], Key::Named(Named::Home)),
+            Action::ToggleHome,
+        ),
+        (bind(&[], Key::Named(Named::F5)), Action::Refresh),
+        (bind(&[], Key::Named(Named::F1)), Action::ToggleHelp),
+    ]);
+    key_binds
+}
+
+/// The default key bindings with the ones configured by the user, which are written like
+/// `Ctrl+Shift+Tab` and map to an action, or to nothing to turn the binding off.
+pub fn key_binds(configured: &BTreeMap<String, Option<Action>>) -> Vec<(KeyBind, Action)> {
+    let mut key_binds = default_key_binds();
+    for (keys, action) in configured {
+        let Some(key_bind) = parse(keys) else {
+            error!("invalid key binding {keys:?}");
+            continue;
+        };
+        key_binds.retain(|(k, _)| *k != key_bind);
+        if let Some(action) = action {
+            key_binds.push((key_bind, *action));
+        }
+    }
+    key_binds
+}
+
+fn parse(keys: &str) -> Option<KeyBind> {
+    let mut parts: Vec<_> = keys.split('+').map(str::trim).collect();
+    let key = match parts.pop()? {
+        // `+` itself, as in `Ctrl++`
+        "" if keys.ends_with("++") => {
+            parts.pop();
+            Key::Character("+".into())
+        }
+        key => parse_key(key)?,
+    };
+    let modifiers = parts
+        .into_iter()
+        .map(|modifier| match modifier.to_lowercase().as_str() {
+            "super" | "logo" => Some(Modifier::Super),
+            "ctrl" | "control" => Some(Modifier::Ctrl),
+            "alt" => Some(Modifier::Alt),
+            "shift" => Some(Modifier::Shift),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(KeyBind { modifiers, key })
+}
+
+fn parse_key(key: &str) -> Option<Key> {
+    let named = match key.to_lowercase().as_str() {
+        "left" => Named::ArrowLeft,
+        "right" => Named::ArrowRight,
+        "up" => Named::ArrowUp,
+        "down" => Named::ArrowDown,
+        "home" => Named::Home,
+        "end" => Named::End,
+        "pageup" => Named::PageUp,
+        "pagedown" => Named::PageDown,
+        "tab" => Named::Tab,
+        "enter" => Named::Enter,
+        "space" => Named::Space,
+        "f1" => Named::F1,
+        "f2" => Named::F2,
+        "f3" => Named::F3,
+        "f4" => Named::F4,
+        "f5" => Named::F5,
+        "f6" => Named::F6,
+        "f7" => Named::F7,
+        "f8" => Named::F8,
+        "f9" => Named::F9,
+        "f10" => Named::F10,
+        "f11" => Named::F11,
+        "f12" => Named::F12,
+        _ if key.chars().count() == 1 => return Some(Key::Character(key.to_lowercase().into())),
+        _ => return None,
+    };
+    Some(Key::Named(named))
+}
+
+/// The action bound to a key press.
+pub fn action(key_binds: &[(KeyBind, Action)], modifiers: Modifiers, key: &Key) -> Option<Action> {
+    // Shift is needed to type some characters, like `?`
+    let modifiers = match key {
+        Key::Character(c) if !c.chars().all(char::is_alphanumeric) => {
+            modifiers.difference(Modifiers::SHIFT)
+        }
+        _ => modifiers,
+    };
+    key_binds
+        .iter()
+        .find(|(key_bind, _)| key_bind.matches(modifiers, key))
+        .map(|(_, action)| *action)
+}
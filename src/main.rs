@@ -2,7 +2,10 @@
 mod config;
 mod app;
 mod app_group;
+mod app_state;
+mod diagnostics;
 mod icon_cache;
+mod key_bind;
 mod localize;
 mod subscriptions;
 mod widgets;
@@ -5,6 +5,7 @@ use cosmic::{
 use notify::{Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::path::PathBuf;
 use tokio::sync::mpsc;
 
 #[derive(Debug, Clone, Copy)]
@@ -44,7 +45,7 @@ pub fn desktop_files<I: 'static + Hash + Copy + Send + Sync + Debug>(
             );
 
             if let Ok(mut watcher) = watcher {
-                for path in freedesktop_desktop_entry::default_paths() {
+                for path in watched_paths() {
                     let _ = watcher.watch(path.as_ref(), RecursiveMode::Recursive);
                 }
 
@@ -57,3 +58,25 @@ pub fn desktop_files<I: 'static + Hash + Copy + Send + Sync + Debug>(
         }),
     )
 }
+
+/// The directories desktop entries are loaded from, including those of Flatpak and Snap,
+/// which are missing from the default paths when the session doesn't add them to `XDG_DATA_DIRS`.
+fn watched_paths() -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = freedesktop_desktop_entry::default_paths()
+        .into_iter()
+        .collect();
+    let extra = [
+        xdg::BaseDirectories::new().ok().map(|dirs| {
+            dirs.get_data_home()
+                .join("flatpak/exports/share/applications")
+        }),
+        Some(PathBuf::from("/var/lib/flatpak/exports/share/applications")),
+        Some(PathBuf::from("/var/lib/snapd/desktop/applications")),
+    ];
+    for path in extra.into_iter().flatten() {
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
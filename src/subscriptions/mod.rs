@@ -1 +1,2 @@
 pub mod desktop_files;
+pub mod status;
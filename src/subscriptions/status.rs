@@ -0,0 +1,96 @@
+use cosmic::{
+    iced::{stream, Subscription},
+    iced_futures::futures::{self, SinkExt},
+};
+use log::error;
+use std::fmt::Debug;
+use std::hash::Hash;
+use tokio::sync::mpsc;
+
+use crate::config::APP_ID;
+
+const OBJECT_PATH: &str = "/com/system76/CosmicAppLibrary/Status";
+
+/// What the library is showing, for status indicators and tests to observe over D-Bus.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Status {
+    pub open: bool,
+    /// The name of the current group.
+    pub group: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The status is served, and updates to it can be sent.
+    Ready(mpsc::UnboundedSender<Status>),
+}
+
+struct StatusServer(Status);
+
+#[zbus::interface(name = "com.system76.CosmicAppLibrary.Status")]
+impl StatusServer {
+    #[zbus(property)]
+    fn open(&self) -> bool {
+        self.0.open
+    }
+
+    #[zbus(property)]
+    fn group(&self) -> String {
+        self.0.group.clone()
+    }
+}
+
+pub fn status<I: 'static + Hash + Copy + Send + Sync + Debug>(id: I) -> Subscription<Event> {
+    Subscription::run_with_id(
+        id,
+        stream::channel(1, move |mut output| async move {
+            let connection = match serve().await {
+                Ok(connection) => connection,
+                Err(err) => {
+                    error!("{:?}", err);
+                    return futures::future::pending().await;
+                }
+            };
+            let (tx, mut rx) = mpsc::unbounded_channel();
+            _ = output.send(Event::Ready(tx)).await;
+
+            while let Some(status) = rx.recv().await {
+                let iface = match connection
+                    .object_server()
+                    .interface::<_, StatusServer>(OBJECT_PATH)
+                    .await
+                {
+                    Ok(iface) => iface,
+                    Err(err) => {
+                        error!("{:?}", err);
+                        continue;
+                    }
+                };
+                let mut server = iface.get_mut().await;
+                let open_changed = server.0.open != status.open;
+                let group_changed = server.0.group != status.group;
+                server.0 = status;
+                if open_changed {
+                    if let Err(err) = server.open_changed(iface.signal_context()).await {
+                        error!("{:?}", err);
+                    }
+                }
+                if group_changed {
+                    if let Err(err) = server.group_changed(iface.signal_context()).await {
+                        error!("{:?}", err);
+                    }
+                }
+            }
+
+            futures::future::pending().await
+        }),
+    )
+}
+
+async fn serve() -> zbus::Result<zbus::Connection> {
+    zbus::connection::Builder::session()?
+        .name(format!("{APP_ID}.Status"))?
+        .serve_at(OBJECT_PATH, StatusServer(Status::default()))?
+        .build()
+        .await
+}
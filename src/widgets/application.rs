@@ -1,36 +1,107 @@
 //! A widget that can be dragged and dropped.
 
 use core::str;
-use std::{borrow::Cow, cell::RefCell, iter, path::PathBuf, str::FromStr};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    iter,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
 use cosmic::{
     iced::{
         alignment::Vertical,
         clipboard::mime::{AllowedMimeTypes, AsMimeTypes},
-        Size, Vector,
+        Color, Size, Vector,
     },
     iced_core::alignment::Horizontal,
     widget::dnd_source,
 };
 
 use cosmic::iced_core::{
-    event, layout, mouse, overlay, renderer, Alignment, Clipboard, Event, Length, Rectangle, Shell,
-    Widget,
+    event,
+    keyboard::{key::Named, Key},
+    layout, mouse, overlay, renderer, touch, window, Alignment, Clipboard, Event, Length, Point,
+    Rectangle, Shell, Widget,
 };
 
 use cosmic::{
-    desktop::DesktopEntryData,
+    desktop::{DesktopEntryData, IconSource},
     iced::widget::{column, text},
     iced_core::widget::{tree, Operation, Tree},
     theme,
-    widget::{button, container},
+    widget::{button, container, icon, tooltip},
     Element,
 };
 
+use once_cell::sync::Lazy;
+
 use crate::app::AppSource;
 
 pub const MIME_TYPE: &str = "text/uri-list";
-const DRAG_THRESHOLD: f32 = 25.0;
+/// How far an app has to be dragged with a pointer before it is moved.
+pub const DRAG_THRESHOLD: f32 = 25.0;
+/// How far an app has to be dragged on a touchscreen before it is moved,
+/// which is higher so that scrolling doesn't start a drag by accident.
+pub const TOUCH_DRAG_THRESHOLD: f32 = 50.0;
+/// How long an app has to be touched before its context menu opens.
+const LONG_PRESS_DURATION: Duration = Duration::from_millis(500);
+/// Icons shown for apps whose icon is missing or can't be found.
+const PLACEHOLDER_ICONS: [&str; 2] = ["application-x-executable", "application-default"];
+/// Extensions which some desktop entries wrongly add to the names of icons in the icon theme.
+const ICON_EXTENSIONS: [&str; 3] = [".png", ".svg", ".xpm"];
+
+/// Whether the library runs in a flatpak sandbox, where the files of the host are under `/run/host`.
+static IN_FLATPAK: Lazy<bool> = Lazy::new(|| Path::new("/.flatpak-info").exists());
+
+/// The path of an icon file as seen from inside the flatpak sandbox, if the library runs in one.
+fn host_path(path: &Path) -> PathBuf {
+    if *IN_FLATPAK && path.starts_with("/usr") {
+        let host = Path::new("/run/host").join(path.strip_prefix("/").unwrap_or(path));
+        if host.exists() {
+            return host;
+        }
+    }
+    path.to_path_buf()
+}
+
+/// The icon of an app, falling back to a generic app icon rather than an empty tile.
+fn app_icon(source: &IconSource) -> icon::Icon {
+    match source {
+        // some desktop entries give the path of the icon as its name
+        IconSource::Name(name) if Path::new(name).is_absolute() => {
+            icon::icon(icon::from_path(host_path(Path::new(name))))
+        }
+        IconSource::Name(name) if !name.is_empty() => icon::icon(named_app_icon(name).into()),
+        IconSource::Path(path) => icon::icon(icon::from_path(host_path(path))),
+        _ => icon::icon(named_app_icon(PLACEHOLDER_ICONS[0]).into()),
+    }
+}
+
+/// The icon of an app from the icon theme, as tiles look it up,
+/// so resolving its path ahead of time caches the icon they show.
+pub fn named_app_icon(name: &str) -> icon::Named {
+    let name = ICON_EXTENSIONS
+        .iter()
+        .find_map(|ext| name.strip_suffix(ext))
+        .unwrap_or(name);
+    icon::from_name(name)
+        .size(128)
+        .fallback(Some(icon::IconFallback::Names(
+            PLACEHOLDER_ICONS.iter().map(|&n| n.into()).collect(),
+        )))
+}
+
+/// The style of names of apps which are normally hidden.
+fn dimmed_text(theme: &cosmic::Theme) -> cosmic::iced_widget::text::Style {
+    let color: Color = theme.cosmic().on_bg_color().into();
+    cosmic::iced_widget::text::Style {
+        color: Some(Color { a: 0.5, ..color }),
+    }
+}
+
 /// A widget that can be dragged and dropped.
 #[allow(missing_debug_implementations)]
 pub struct ApplicationButton<'a, Message> {
@@ -40,13 +111,28 @@ pub struct ApplicationButton<'a, Message> {
 
     on_right_release: Box<dyn Fn(Rectangle) -> Message + 'a>,
 
+    on_middle_release: Option<Message>,
+
+    // How far a long press may move before it is a drag instead
+    drag_threshold: f32,
+
+    // Whether the menu key opens the context menu of this app
+    selected: bool,
+
     // Optional icon, and text
     source_icon: Option<Element<'a, Message>>,
+
+    // Optional badge shown when the app is pinned to the app tray
+    pinned_icon: Option<Element<'a, Message>>,
+
+    // Optional badge shown when other variants of the app are in the context menu
+    variants_icon: Option<Element<'a, Message>>,
 }
 
 impl<'a, Message: Clone + 'static> ApplicationButton<'a, Message> {
     /// Creates a new [`ApplicationButton`].
     #[must_use]
+    #[cfg_attr(not(feature = "a11y"), allow(unused_variables))]
     pub fn new(
         DesktopEntryData {
             name,
@@ -56,11 +142,20 @@ impl<'a, Message: Clone + 'static> ApplicationButton<'a, Message> {
         }: &'a DesktopEntryData,
         on_right_release: impl Fn(Rectangle) -> Message + 'a,
         on_pressed: Option<Message>,
+        on_middle_release: Option<Message>,
         source: Option<&AppSource>,
         selected: bool,
         on_start: Option<Message>,
         on_finish: Option<Message>,
         on_cancel: Option<Message>,
+        // None if the app can't be dragged
+        drag_threshold: Option<f32>,
+        is_pinned: bool,
+        is_hidden: bool,
+        has_variants: bool,
+        two_line_name: bool,
+        // the index of the app among the shown apps, and their count
+        position: (usize, usize),
     ) -> Self {
         let cosmic::cosmic_theme::Spacing {
             space_xxs, space_s, ..
@@ -85,12 +180,45 @@ impl<'a, Message: Clone + 'static> ApplicationButton<'a, Message> {
             }
             None => (None, 0),
         };
-        let max_name_len = 27 - source_suffix_len;
+        let pinned_icon = is_pinned.then(|| {
+            Element::from(
+                container(icon::icon(
+                    icon::from_name("view-pin-symbolic").size(16).into(),
+                ))
+                .class(cosmic::theme::Container::Card)
+                .width(Length::Fixed(24.0))
+                .height(Length::Fixed(24.0))
+                .align_x(Horizontal::Center)
+                .align_y(Vertical::Center),
+            )
+        });
+        #[cfg(feature = "a11y")]
+        let label = match source {
+            Some(source) => format!("{name} ({})", source.description()),
+            None => name.to_string(),
+        };
+        let variants_icon = has_variants.then(|| {
+            Element::from(
+                container(icon::icon(
+                    icon::from_name("pan-down-symbolic").size(16).into(),
+                ))
+                .class(cosmic::theme::Container::Card)
+                .width(Length::Fixed(24.0))
+                .height(Length::Fixed(24.0))
+                .align_x(Horizontal::Center)
+                .align_y(Vertical::Center),
+            )
+        });
+        // the characters which fit in the name, and how many are kept when it is shortened
+        let (max_name_len, shortened_len) = if two_line_name { (27, 24) } else { (14, 11) };
+        let max_name_len = max_name_len - source_suffix_len.min(max_name_len);
         let name = if name.len() > max_name_len {
             if let Some(source) = source {
-                format!("{name:.17}... ({source})")
+                let shortened: String = name.chars().take(shortened_len - 7).collect();
+                format!("{shortened}... ({source})")
             } else {
-                format!("{name:.24}...")
+                let shortened: String = name.chars().take(shortened_len).collect();
+                format!("{shortened}...")
             }
         } else {
             if let Some(source) = source {
@@ -101,51 +229,75 @@ impl<'a, Message: Clone + 'static> ApplicationButton<'a, Message> {
         };
         let path_ = path.clone();
         let image_clone = image.clone();
-        let content = dnd_source(
-            button::custom(
-                column![
-                    image
-                        .as_cosmic_icon()
-                        .width(Length::Fixed(72.0))
-                        .height(Length::Fixed(72.0)),
-                    text(name)
-                        .align_x(Horizontal::Center)
-                        .size(14)
-                        .height(Length::Fixed(40.0))
-                ]
-                .width(Length::Fixed(120.0))
-                .height(Length::Fixed(120.0))
-                .spacing(space_xxs)
-                .align_x(Alignment::Center)
-                .width(Length::Fill),
-            )
-            .selected(selected)
-            .width(Length::FillPortion(1))
-            .class(theme::Button::IconVertical)
-            .padding(space_s)
-            .on_press_maybe(on_pressed.clone()),
-        )
-        .drag_icon(move || {
-            (
-                image_clone
-                    .as_cosmic_icon()
+        let tile = button::custom(
+            column![
+                app_icon(image)
                     .width(Length::Fixed(72.0))
-                    .height(Length::Fixed(72.0))
-                    .into(),
-                tree::State::None,
-            )
-        })
-        .drag_content(move || AppletString(path_.clone().unwrap()))
-        .on_start(on_start)
-        .on_cancel(on_cancel)
-        .on_finish(on_finish)
-        .into();
+                    .height(Length::Fixed(72.0)),
+                text(name)
+                    .align_x(Horizontal::Center)
+                    .size(14)
+                    .height(Length::Fixed(if two_line_name { 40.0 } else { 20.0 }))
+                    .class(if is_hidden {
+                        theme::Text::Custom(dimmed_text)
+                    } else {
+                        theme::Text::Default
+                    })
+            ]
+            .width(Length::Fixed(120.0))
+            .height(Length::Fixed(if two_line_name { 120.0 } else { 100.0 }))
+            .spacing(space_xxs)
+            .align_x(Alignment::Center)
+            .width(Length::Fill),
+        )
+        .selected(selected)
+        .width(Length::FillPortion(1))
+        .class(theme::Button::IconVertical)
+        .padding(space_s)
+        .on_press_maybe(on_pressed.clone());
+        // screen readers announce the full name and source, and where the app is among the others
+        #[cfg(feature = "a11y")]
+        let tile = tile.name(label).description(crate::fl!(
+            "app-position",
+            position = position.0 + 1,
+            count = position.1
+        ));
+        let tile: Element<'a, Message> = match source {
+            Some(source) => {
+                tooltip(tile, text(source.description()), tooltip::Position::Bottom).into()
+            }
+            None => tile.into(),
+        };
+        let content = match drag_threshold {
+            Some(drag_threshold) => dnd_source(tile)
+                .drag_threshold(drag_threshold)
+                .drag_icon(move || {
+                    (
+                        app_icon(&image_clone)
+                            .width(Length::Fixed(72.0))
+                            .height(Length::Fixed(72.0))
+                            .into(),
+                        tree::State::None,
+                    )
+                })
+                .drag_content(move || AppletString(path_.clone().unwrap()))
+                .on_start(on_start)
+                .on_cancel(on_cancel)
+                .on_finish(on_finish)
+                .into(),
+            None => tile,
+        };
         Self {
             path: path.clone().unwrap(),
             content,
             on_right_release: Box::new(on_right_release),
+            on_middle_release,
+            drag_threshold: drag_threshold.unwrap_or(TOUCH_DRAG_THRESHOLD),
+            selected,
 
             source_icon,
+            pinned_icon,
+            variants_icon,
         }
     }
 }
@@ -167,12 +319,16 @@ where
     fn children(&self) -> Vec<Tree> {
         iter::once(Tree::new(&self.content))
             .chain(self.source_icon.as_ref().map(|i| Tree::new(i)))
+            .chain(self.pinned_icon.as_ref().map(|i| Tree::new(i)))
+            .chain(self.variants_icon.as_ref().map(|i| Tree::new(i)))
             .collect()
     }
 
     fn diff(&mut self, tree: &mut Tree) {
         let mut children: Vec<_> = iter::once(&mut self.content)
             .chain(self.source_icon.as_mut())
+            .chain(self.pinned_icon.as_mut())
+            .chain(self.variants_icon.as_mut())
             .collect();
         tree.diff_children(children.as_mut_slice());
     }
@@ -203,8 +359,10 @@ where
                     .layout(content_state, renderer, limits)
             },
             self.source_icon.as_ref(),
-            |renderer, limits, icon| {
-                let icon_state = &mut tree.borrow_mut().children[1];
+            self.pinned_icon.as_ref(),
+            self.variants_icon.as_ref(),
+            |renderer, limits, child, icon| {
+                let icon_state = &mut tree.borrow_mut().children[child];
                 icon.as_widget().layout(icon_state, renderer, limits)
             },
         )
@@ -231,15 +389,18 @@ where
             viewport,
         );
 
-        if let Some((icon, (l, bounds))) = self.source_icon.as_ref().zip(
-            layout
-                .children()
-                .nth(1)
-                .and_then(|l| viewport.intersection(&l.bounds()).map(|b| (l, b))),
-        ) {
+        let badges = self
+            .source_icon
+            .iter()
+            .chain(self.pinned_icon.iter())
+            .chain(self.variants_icon.iter());
+        for (child, (icon, l)) in badges.zip(layout.children().skip(1)).enumerate() {
+            let Some(bounds) = viewport.intersection(&l.bounds()) else {
+                continue;
+            };
             renderer.with_layer(bounds, |renderer| {
                 icon.as_widget().draw(
-                    &tree.children[1],
+                    &tree.children[child + 1],
                     renderer,
                     theme,
                     renderer_style,
@@ -302,6 +463,21 @@ where
         shell: &mut Shell<'_, Message>,
         viewport: &Rectangle,
     ) -> event::Status {
+        let state = tree.state.downcast_mut::<State>();
+
+        if let Event::Touch(
+            touch::Event::FingerLifted { id, .. } | touch::Event::FingerLost { id, .. },
+        ) = &event
+        {
+            if state.long_press.is_some_and(|(finger, ..)| finger == *id) {
+                state.long_press = None;
+                // the context menu opened, so lifting the finger doesn't launch the app
+                if std::mem::take(&mut state.long_pressed) {
+                    return event::Status::Captured;
+                }
+            }
+        }
+
         let ret = self.content.as_widget_mut().on_event(
             &mut tree.children[0],
             event.clone(),
@@ -313,7 +489,50 @@ where
             viewport,
         );
 
-        let state = tree.state.downcast_mut::<State>();
+        // the Menu key or Shift+F10 open the context menu of the selected app
+        if let Event::Keyboard(cosmic::iced_core::keyboard::Event::KeyPressed {
+            key,
+            modifiers,
+            ..
+        }) = &event
+        {
+            let menu_key = matches!(key, Key::Named(Named::ContextMenu))
+                || (modifiers.shift() && matches!(key, Key::Named(Named::F10)));
+            if self.selected && menu_key {
+                shell.publish(self.on_right_release.as_ref()(layout.bounds()));
+                return event::Status::Captured;
+            }
+        }
+
+        match &event {
+            Event::Touch(touch::Event::FingerPressed { id, position })
+                if layout.bounds().contains(*position) =>
+            {
+                let now = Instant::now();
+                state.long_press = Some((*id, *position, now));
+                state.long_pressed = false;
+                shell.request_redraw(window::RedrawRequest::At(now + LONG_PRESS_DURATION));
+            }
+            Event::Touch(touch::Event::FingerMoved { id, position }) => {
+                if state.long_press.is_some_and(|(finger, start, _)| {
+                    finger == *id && start.distance(*position) > self.drag_threshold
+                }) {
+                    state.long_press = None;
+                }
+            }
+            Event::Window(window::Event::RedrawRequested(now)) if !state.long_pressed => {
+                if let Some((_, _, start)) = state.long_press {
+                    if now.duration_since(start) >= LONG_PRESS_DURATION {
+                        state.long_pressed = true;
+                        shell.publish(self.on_right_release.as_ref()(layout.bounds()));
+                    } else {
+                        shell
+                            .request_redraw(window::RedrawRequest::At(start + LONG_PRESS_DURATION));
+                    }
+                }
+            }
+            _ => {}
+        }
 
         if cursor_position.is_over(layout.bounds()) {
             match &event {
@@ -328,6 +547,23 @@ where
                         return event::Status::Captured;
                     }
                 }
+                Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Middle))
+                    if self.on_middle_release.is_some() =>
+                {
+                    state.middle_press = true;
+                    return event::Status::Captured;
+                }
+                Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Middle)) => {
+                    if let Some(message) = self
+                        .on_middle_release
+                        .clone()
+                        .filter(|_| state.middle_press)
+                    {
+                        shell.publish(message);
+                        state.middle_press = false;
+                        return event::Status::Captured;
+                    }
+                }
                 _ => {}
             }
         }
@@ -351,6 +587,20 @@ where
             renderer,
         )
     }
+
+    #[cfg(feature = "a11y")]
+    fn a11y_nodes(
+        &self,
+        layout: layout::Layout<'_>,
+        state: &Tree,
+        cursor_position: mouse::Cursor,
+    ) -> cosmic::iced_accessibility::A11yTree {
+        self.content.as_widget().a11y_nodes(
+            layout.children().next().unwrap(),
+            &state.children[0],
+            cursor_position,
+        )
+    }
 }
 
 /// Computes the layout of a [`ApplicationButton`].
@@ -363,7 +613,9 @@ pub fn layout<'a, Renderer, M>(
     max_width: u32,
     layout_content: impl FnOnce(&Renderer, &layout::Limits) -> layout::Node,
     icon: Option<&Element<'a, M>>,
-    layout_icon: impl FnOnce(&Renderer, &layout::Limits, &Element<'a, M>) -> layout::Node,
+    pinned_icon: Option<&Element<'a, M>>,
+    variants_icon: Option<&Element<'a, M>>,
+    mut layout_icon: impl FnMut(&Renderer, &layout::Limits, usize, &Element<'a, M>) -> layout::Node,
 ) -> layout::Node {
     let limits = limits
         .loose()
@@ -375,17 +627,33 @@ pub fn layout<'a, Renderer, M>(
     let content = layout_content(renderer, &limits);
     let size = limits.resolve(width, height, content.size());
     let mut children = vec![content];
-    let app_icon_node = &children[0].children()[0].children()[0].children()[0];
+    let app_icon_bounds = children[0].children()[0].children()[0].children()[0].bounds();
+    let badge_limits = layout::Limits::new(Size::new(24., 24.), Size::new(24., 24.));
+    let mut child = 1;
     if let Some(icon) = icon {
-        let app_icon_size = app_icon_node.size();
-        let mut icon_node = layout_icon(
-            renderer,
-            &layout::Limits::new(Size::new(24., 24.), Size::new(24., 24.)),
-            icon,
-        );
-        icon_node = icon_node.move_to(app_icon_node.bounds().position());
+        let mut icon_node = layout_icon(renderer, &badge_limits, child, icon);
+        icon_node = icon_node.move_to(app_icon_bounds.position());
         // translate to the bottom right corner
-        icon_node = icon_node.translate(Vector::new(app_icon_size.width, app_icon_size.height));
+        icon_node = icon_node.translate(Vector::new(app_icon_bounds.width, app_icon_bounds.height));
+
+        children.push(icon_node);
+        child += 1;
+    }
+    if let Some(icon) = pinned_icon {
+        let mut icon_node = layout_icon(renderer, &badge_limits, child, icon);
+        icon_node = icon_node.move_to(app_icon_bounds.position());
+        // translate to the top right corner
+        icon_node = icon_node.translate(Vector::new(app_icon_bounds.width, 0.0));
+
+        children.push(icon_node);
+        child += 1;
+    }
+    if let Some(icon) = variants_icon {
+        let mut icon_node = layout_icon(renderer, &badge_limits, child, icon);
+        icon_node = icon_node.move_to(app_icon_bounds.position());
+        // translate to the bottom left corner, without leaving the tile
+        let offset = icon_node.bounds().width.min(app_icon_bounds.x);
+        icon_node = icon_node.translate(Vector::new(-offset, app_icon_bounds.height));
 
         children.push(icon_node);
     }
@@ -408,8 +676,14 @@ impl TryFrom<(Vec<u8>, String)> for AppletString {
 
     fn try_from((value, mime): (Vec<u8>, String)) -> Result<Self, Self::Error> {
         if mime == MIME_TYPE {
+            // file managers may offer several files, and the list may contain comments
+            let uri = str::from_utf8(&value)?
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty() && !line.starts_with('#'))
+                .ok_or_else(|| anyhow::anyhow!("Empty uri list"))?;
             Ok(AppletString(
-                url::Url::from_str(str::from_utf8(&value)?)?
+                url::Url::from_str(uri)?
                     .to_file_path()
                     .map_err(|_| anyhow::anyhow!("Invalid file path"))?,
             ))
@@ -440,4 +714,9 @@ impl AsMimeTypes for AppletString {
 #[derive(Debug, Default, Clone)]
 pub struct State {
     right_press: bool,
+    middle_press: bool,
+    // the finger held on the app, where it was put down, and when
+    long_press: Option<(touch::Finger, Point, Instant)>,
+    // whether the held finger already opened the context menu
+    long_pressed: bool,
 }
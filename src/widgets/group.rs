@@ -0,0 +1,200 @@
+//! A group tile which can be right-clicked.
+
+use cosmic::iced_core::{
+    event, layout, mouse, overlay, renderer, Clipboard, Event, Length, Rectangle, Shell, Size,
+    Vector, Widget,
+};
+use cosmic::{
+    iced_core::widget::{tree, Operation, Tree},
+    Element,
+};
+
+/// Wraps a group tile, reporting its bounds when it is right-clicked.
+#[allow(missing_debug_implementations)]
+pub struct GroupButton<'a, Message> {
+    content: Element<'a, Message>,
+
+    on_right_release: Box<dyn Fn(Rectangle) -> Message + 'a>,
+}
+
+impl<'a, Message: Clone + 'static> GroupButton<'a, Message> {
+    /// Creates a new [`GroupButton`].
+    #[must_use]
+    pub fn new(
+        content: impl Into<Element<'a, Message>>,
+        on_right_release: impl Fn(Rectangle) -> Message + 'a,
+    ) -> Self {
+        Self {
+            content: content.into(),
+            on_right_release: Box::new(on_right_release),
+        }
+    }
+}
+
+impl<'a, Message> From<GroupButton<'a, Message>> for Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    fn from(group_button: GroupButton<'a, Message>) -> Element<'a, Message> {
+        Element::new(group_button)
+    }
+}
+
+impl<'a, Message> Widget<Message, cosmic::Theme, cosmic::Renderer> for GroupButton<'a, Message>
+where
+    Message: Clone,
+{
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&mut self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_mut(&mut self.content));
+    }
+
+    fn size(&self) -> Size<Length> {
+        self.content.as_widget().size()
+    }
+
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &cosmic::Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        self.content
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits)
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut cosmic::Renderer,
+        theme: &cosmic::theme::Theme,
+        renderer_style: &renderer::Style,
+        layout: layout::Layout<'_>,
+        cursor_position: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            renderer_style,
+            layout,
+            cursor_position,
+            viewport,
+        );
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: layout::Layout<'_>,
+        renderer: &cosmic::Renderer,
+        operation: &mut dyn Operation<()>,
+    ) {
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, operation);
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: layout::Layout<'_>,
+        renderer: &cosmic::Renderer,
+        translation: Vector,
+    ) -> Option<overlay::Element<'b, Message, cosmic::Theme, cosmic::Renderer>> {
+        self.content
+            .as_widget_mut()
+            .overlay(&mut tree.children[0], layout, renderer, translation)
+    }
+
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: layout::Layout<'_>,
+        cursor_position: mouse::Cursor,
+        renderer: &cosmic::Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        let ret = self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event.clone(),
+            layout,
+            cursor_position,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        );
+
+        let state = tree.state.downcast_mut::<State>();
+
+        if cursor_position.is_over(layout.bounds()) {
+            match &event {
+                Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right)) => {
+                    state.right_press = true;
+                    return event::Status::Captured;
+                }
+                Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Right)) => {
+                    if state.right_press {
+                        shell.publish(self.on_right_release.as_ref()(layout.bounds()));
+                        state.right_press = false;
+                        return event::Status::Captured;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        ret
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: layout::Layout<'_>,
+        cursor_position: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &cosmic::Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor_position,
+            viewport,
+            renderer,
+        )
+    }
+
+    #[cfg(feature = "a11y")]
+    fn a11y_nodes(
+        &self,
+        layout: layout::Layout<'_>,
+        state: &Tree,
+        cursor_position: mouse::Cursor,
+    ) -> cosmic::iced_accessibility::A11yTree {
+        self.content
+            .as_widget()
+            .a11y_nodes(layout, &state.children[0], cursor_position)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct State {
+    right_press: bool,
+}